@@ -0,0 +1,77 @@
+// A custom statistic gathered over a simulation: which hand category wins the pot, and how
+// often the winner was the player with the best two hole cards by the Chen formula.
+//
+//     cargo run --release --example aggregator
+
+use pokersim::aggregate::{downcast, Aggregator};
+use pokersim::filter::{category_value, CATEGORIES};
+use pokersim::folding::chen_score;
+use pokersim::simulator::{self, SimulationConfig};
+use pokersim::GameResult;
+
+#[derive(Default)]
+struct WinningHands {
+    // Pots by the category of the winning hand, split pots counted once
+    categories: [usize; 10],
+    favourite_won: usize,
+    pots: usize,
+}
+
+impl Aggregator for WinningHands {
+    fn empty(&self) -> Box<dyn Aggregator> {
+        Box::new(WinningHands::default())
+    }
+
+    fn update(&mut self, result: &GameResult) {
+        let winner = result.winners[0];
+        self.categories[category_value(&result.hand_ranks[winner]) as usize] += 1;
+        let favourite = (0..result.hole_cards.len())
+            .max_by(|&a, &b| {
+                let score = |seat: usize| chen_score(&result.hole_cards[seat]);
+                score(a).partial_cmp(&score(b)).unwrap()
+            })
+            .unwrap();
+        if result.winners.contains(&favourite) {
+            self.favourite_won += 1;
+        }
+        self.pots += 1;
+    }
+
+    fn merge(&mut self, other: Box<dyn Aggregator>) {
+        let other = downcast::<WinningHands>(other);
+        for (total, count) in self.categories.iter_mut().zip(other.categories) {
+            *total += count;
+        }
+        self.favourite_won += other.favourite_won;
+        self.pots += other.pots;
+    }
+
+    fn report(&self) {
+        let pots = self.pots.max(1) as f64;
+        println!("Winning hands over {} pots:", self.pots);
+        for (name, count) in CATEGORIES.iter().zip(self.categories) {
+            println!("  {:<14} {:>7.3}%", name, count as f64 / pots * 100.0);
+        }
+        println!(
+            "The best starting hand won {:.2}% of pots",
+            self.favourite_won as f64 / pots * 100.0
+        );
+    }
+}
+
+fn main() {
+    let config = SimulationConfig {
+        seed: 7,
+        ..SimulationConfig::new(200_000, 6)
+    };
+    let stats = simulator::run_simulation(
+        &config,
+        &[],
+        vec![Box::new(WinningHands::default())],
+        |_| {},
+    );
+    // Aggregators come back merged over every worker, in the order they were passed
+    for aggregator in &stats.aggregators {
+        aggregator.report();
+    }
+}
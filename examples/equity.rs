@@ -0,0 +1,39 @@
+// Equity of a hand against a range on a flop, counted exactly and estimated by sampling.
+//
+//     cargo run --release --example equity
+
+use pokersim::equity::{self, Holding, Scenario};
+use pokersim::notation::parse_cards;
+use pokersim::range::Range;
+
+fn main() -> Result<(), String> {
+    let hero = Holding::Known(parse_cards("Ah Kh")?);
+    let villain = Holding::Range(Range::parse("QQ+,AQs+,JTs")?);
+    let board = parse_cards("Qh 7h 2c")?;
+    let scenario = Scenario::new(vec![hero, villain], board)?;
+
+    // Every runout of every hand in the range; branches already won on the turn are
+    // credited without dealing the river
+    let (results, deals) = equity::exact_equity_by_street(&scenario)?;
+    println!("Exact over {} deals:", deals);
+    for (holding, seat) in scenario.seats.iter().zip(&results) {
+        let equity = seat.equity() / deals as f64;
+        println!("  {:<14} {:.3}%", holding.to_string(), equity * 100.0);
+    }
+
+    // The same seed always deals the same cards
+    let trials = 200_000;
+    let results = equity::sample_equity(&scenario, trials, 42);
+    println!("Sampled over {} deals:", trials);
+    for (holding, seat) in scenario.seats.iter().zip(&results) {
+        let (equity, low, high) = seat.equity_interval(trials);
+        println!(
+            "  {:<14} {:.3}% (95% CI {:.3}% to {:.3}%)",
+            holding.to_string(),
+            equity * 100.0,
+            low * 100.0,
+            high * 100.0
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+// How a six-handed table's results change by position when players fold preflop like a
+// typical 6-max game, and how precise the estimates are.
+//
+//     cargo run --release --example positions
+
+use pokersim::folding::{position_name, FoldModel, TablePreset};
+use pokersim::simulator::{self, SimulationConfig};
+
+fn main() {
+    let players = 6;
+    let config = SimulationConfig {
+        fold_model: Some(FoldModel::for_table(TablePreset::SixMax, players)),
+        // Stop once every equity is known to within a quarter of a percent
+        tolerance: Some(0.0025),
+        seed: 1,
+        ..SimulationConfig::new(2_000_000, players)
+    };
+    let stats = simulator::run_simulation(&config, &[], Vec::new(), |_| {});
+    let games = stats.games_played;
+    println!(
+        "{} games{}",
+        games,
+        if stats.converged { ", converged" } else { "" }
+    );
+    let model = config.fold_model.as_ref().unwrap();
+    for (seat, results) in stats.seats.iter().enumerate() {
+        let (equity, low, high) = results.equity_interval(games);
+        println!(
+            "{:<4} plays {:>3.0}%  wins {:>6.2}% of pots  (95% CI {:.2}% to {:.2}%)",
+            position_name(seat, players),
+            model.vpip(seat) * 100.0,
+            equity * 100.0,
+            low * 100.0,
+            high * 100.0
+        );
+    }
+}
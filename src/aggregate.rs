@@ -0,0 +1,26 @@
+use crate::GameResult;
+use std::any::Any;
+
+// A statistic gathered over every game of a simulation, so new research metrics can be
+// added without touching the simulation loop. Unlike a HandHook it needs no locking: every
+// worker fills an empty copy of its own and the copies are merged once the work is done.
+pub trait Aggregator: Any + Send + Sync {
+    // A fresh aggregator of the same kind and settings, holding no games yet
+    fn empty(&self) -> Box<dyn Aggregator>;
+
+    fn update(&mut self, result: &GameResult);
+
+    // Add in the games of `other`, always an aggregator made by `empty`; see `downcast`
+    fn merge(&mut self, other: Box<dyn Aggregator>);
+
+    // Called once after the last game, e.g. to print a summary
+    fn report(&self);
+}
+
+// `other` in Aggregator::merge as the concrete type it was made as
+pub fn downcast<T: Aggregator>(other: Box<dyn Aggregator>) -> Box<T> {
+    let other: Box<dyn Any> = other;
+    other
+        .downcast()
+        .unwrap_or_else(|_| panic!("merged aggregators of different kinds"))
+}
@@ -0,0 +1,40 @@
+// Counts heap allocations when built with the `alloc-stats` feature. Off by default, as
+// the shared counter adds an atomic increment to every allocation.
+
+#[cfg(feature = "alloc-stats")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    pub static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+    // Must be registered with #[global_allocator] by the binary for counting to happen
+    pub struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+pub use counting::CountingAllocator;
+
+// Allocations since startup, or None when counting is compiled out
+pub fn allocations() -> Option<u64> {
+    #[cfg(feature = "alloc-stats")]
+    return Some(counting::ALLOCATIONS.load(std::sync::atomic::Ordering::Relaxed));
+    #[cfg(not(feature = "alloc-stats"))]
+    None
+}
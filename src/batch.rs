@@ -0,0 +1,253 @@
+use crate::bitboard::CardSet;
+use crate::draw::DiscardPolicy;
+use crate::folding::FoldModel;
+use crate::notation::cards_to_string;
+use crate::profile::{Phase, Profile};
+use crate::rng::SimRng;
+use crate::variant::Variant;
+use crate::{Card, GameResult, HandRank};
+use std::time::Instant;
+
+// A batch of deals in struct-of-arrays layout: the hole cards of every game and seat
+// sit in one contiguous array and all boards in another, so evaluation walks memory
+// linearly and other backends can consume the arrays as they are.
+pub struct GameBatch {
+    num_games: usize,
+    num_players: usize,
+    variant: Variant,
+    // Indexed by game, then seat, then card
+    hole_cards: Vec<Card>,
+    // Indexed by game, then card
+    boards: Vec<Card>,
+    // The cards left in each game's deck once the hands and board are out, which the
+    // seats draw their replacements from in turn; empty in games without a draw
+    stubs: Vec<Card>,
+    // What each seat threw away at the draw, indexed by game, then seat; empty until
+    // `draw` is called
+    discards: Vec<CardSet>,
+}
+
+impl GameBatch {
+    pub fn deal(
+        num_games: usize,
+        num_players: usize,
+        variant: Variant,
+        rng: &mut SimRng,
+    ) -> GameBatch {
+        GameBatch::deal_profiled(num_games, num_players, variant, rng, None)
+    }
+
+    // Deal, timing the shuffle and the deal of every game into `profile` when given
+    pub fn deal_profiled(
+        num_games: usize,
+        num_players: usize,
+        variant: Variant,
+        rng: &mut SimRng,
+        mut profile: Option<&mut Profile>,
+    ) -> GameBatch {
+        let hole_size = variant.hole_cards();
+        let mut hole_cards = Vec::with_capacity(num_games * num_players * hole_size);
+        let board_size = variant.board_cards();
+        let mut boards = Vec::with_capacity(num_games * board_size);
+        let stub_size = GameBatch::stub_size(num_players, variant);
+        let mut stubs = Vec::with_capacity(num_games * stub_size);
+        for _ in 0..num_games {
+            let started = profile.is_some().then(Instant::now);
+            let mut deck = variant.deck();
+            deck.shuffle(rng);
+            if let (Some(profile), Some(started)) = (profile.as_deref_mut(), started) {
+                profile.record(Phase::Shuffle, started);
+            }
+
+            let started = profile.is_some().then(Instant::now);
+            for _ in 0..num_players * hole_size {
+                hole_cards.push(deck.deal().unwrap());
+            }
+            for _ in 0..board_size {
+                boards.push(deck.deal().unwrap());
+            }
+            for _ in 0..stub_size {
+                stubs.push(deck.deal().unwrap());
+            }
+            if let (Some(profile), Some(started)) = (profile.as_deref_mut(), started) {
+                profile.record(Phase::Deal, started);
+            }
+        }
+        GameBatch {
+            num_games,
+            num_players,
+            variant,
+            hole_cards,
+            boards,
+            stubs,
+            discards: Vec::new(),
+        }
+    }
+
+    // Cards left in a game's deck for the draw
+    fn stub_size(num_players: usize, variant: Variant) -> usize {
+        if variant.draw_cards() == 0 {
+            return 0;
+        }
+        variant.deck_size() - variant.board_cards() - num_players * variant.hole_cards()
+    }
+
+    // The draw, in games that have one: every seat in turn throws away the cards `policy`
+    // picks and is dealt as many from what is left of the deck
+    pub fn draw(&mut self, policy: &dyn DiscardPolicy) {
+        let stub_size = GameBatch::stub_size(self.num_players, self.variant);
+        if stub_size == 0 {
+            return;
+        }
+        let hole_size = self.variant.hole_cards();
+        let mut discards = Vec::with_capacity(self.num_games * self.num_players);
+        for (hands, stub) in self
+            .hole_cards
+            .chunks_mut(self.num_players * hole_size)
+            .zip(self.stubs.chunks(stub_size))
+        {
+            let mut stub = stub.iter();
+            for hand in hands.chunks_mut(hole_size) {
+                let thrown = policy.discard(hand);
+                for card in hand.iter_mut().filter(|card| thrown.contains(**card)) {
+                    *card = *stub.next().expect("the deck ran out of cards at the draw");
+                }
+                discards.push(thrown);
+            }
+        }
+        self.discards = discards;
+    }
+
+    pub fn len(&self) -> usize {
+        self.num_games
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_games == 0
+    }
+
+    pub fn hole_cards(&self, game: usize, seat: usize) -> &[Card] {
+        let hole_size = self.variant.hole_cards();
+        let start = (game * self.num_players + seat) * hole_size;
+        &self.hole_cards[start..start + hole_size]
+    }
+
+    pub fn board(&self, game: usize) -> &[Card] {
+        let board_size = self.variant.board_cards();
+        &self.boards[game * board_size..(game + 1) * board_size]
+    }
+
+    // Panic if any card appears twice among the hole cards and board of `game`
+    pub fn check_distinct_cards(&self, game: usize) {
+        let dealt = self.num_players * self.variant.hole_cards();
+        let hole_cards = &self.hole_cards[game * dealt..(game + 1) * dealt];
+        let mut seen = CardSet::new();
+        for &card in hole_cards.iter().chain(self.board(game)) {
+            assert!(
+                !seen.contains(card),
+                "card {} dealt twice in game {} of batch (hole cards {}, board {})",
+                card,
+                game,
+                cards_to_string(hole_cards),
+                cards_to_string(self.board(game))
+            );
+            seen.insert(card);
+        }
+    }
+
+    // Evaluate one game of the batch straight from the packed arrays, first folding
+    // seats preflop when a fold model is given, drawing its decisions from `rng`
+    pub fn play(
+        &self,
+        game: usize,
+        fold_model: Option<&FoldModel>,
+        rng: &mut SimRng,
+    ) -> GameResult {
+        self.play_profiled(game, fold_model, rng, None)
+    }
+
+    // Play, timing each player's evaluation and the showdown into `profile` when given
+    pub fn play_profiled(
+        &self,
+        game: usize,
+        fold_model: Option<&FoldModel>,
+        rng: &mut SimRng,
+        mut profile: Option<&mut Profile>,
+    ) -> GameResult {
+        let board = self.board(game);
+        let dealt: Vec<&[Card]> = (0..self.num_players)
+            .map(|seat| self.hole_cards(game, seat))
+            .collect();
+        let hole_cards: Vec<Vec<Card>> = dealt
+            .iter()
+            .map(|hole_cards| self.variant.keep(hole_cards, board))
+            .collect();
+        let hand_ranks: Vec<HandRank> = hole_cards
+            .iter()
+            .map(|hole_cards| {
+                let started = profile.is_some().then(Instant::now);
+                let hand_rank = self.variant.evaluate(hole_cards, board).to_hand_rank();
+                if let (Some(profile), Some(started)) = (profile.as_deref_mut(), started) {
+                    profile.record(Phase::Evaluate, started);
+                }
+                hand_rank
+            })
+            .collect();
+
+        let started = profile.is_some().then(Instant::now);
+
+        let folded = match fold_model {
+            Some(model) => model.folds(dealt.iter().copied(), rng),
+            None => vec![false; self.num_players],
+        };
+        let mut result =
+            GameResult::from_showdown(self.variant, hole_cards, board.to_vec(), hand_ranks, folded);
+        if !self.discards.is_empty() {
+            let discards = &self.discards[game * self.num_players..][..self.num_players];
+            for (discarded, discards) in result.discarded.iter_mut().zip(discards) {
+                *discarded = discards.to_vec();
+            }
+        }
+        // Games that discard without drawing keep fewer cards than they deal
+        for ((discarded, kept), dealt) in result
+            .discarded
+            .iter_mut()
+            .zip(&result.hole_cards)
+            .zip(&dealt)
+            .filter(|((_, kept), dealt)| kept.len() < dealt.len())
+        {
+            let kept = CardSet::from(&kept[..]);
+            discarded.extend(dealt.iter().filter(|&&card| !kept.contains(card)));
+        }
+        if let (Some(profile), Some(started)) = (profile, started) {
+            profile.record(Phase::Showdown, started);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draw::StandardDraw;
+    use crate::rng::{task_rng, Purpose};
+
+    #[test]
+    fn six_handed_draw() {
+        let policy = StandardDraw::default();
+        assert!(Variant::Draw.max_players(policy.max_draw) >= 6);
+        let mut rng = task_rng(268, Purpose::Deal, 0);
+        let mut batch = GameBatch::deal(500, 6, Variant::Draw, &mut rng);
+        batch.draw(&policy);
+        for game in 0..batch.len() {
+            // Replacements come from the deck, never another seat's cards
+            batch.check_distinct_cards(game);
+            let result = batch.play(game, None, &mut rng);
+            assert_eq!(result.hole_cards.len(), 6);
+            for (hand, discarded) in result.hole_cards.iter().zip(&result.discarded) {
+                assert_eq!(hand.len(), 5);
+                assert!(discarded.len() <= policy.max_draw);
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::notation::{RANKS, SUITS};
+use crate::{Card, Suit};
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Not};
+
+// A set of cards as a 52-bit mask, bit `Card::index()` per card. Suits occupy 13
+// consecutive bits each, lowest rank first, so one suit's ranks are a shift and a mask
+// away and set operations are single instructions.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CardSet(pub u64);
+
+const SUIT_BITS: u64 = 0x1fff;
+
+impl CardSet {
+    pub const EMPTY: CardSet = CardSet(0);
+    pub const FULL: CardSet = CardSet((1 << 52) - 1);
+
+    pub fn new() -> CardSet {
+        CardSet::EMPTY
+    }
+
+    pub fn insert(&mut self, card: Card) {
+        self.0 |= 1 << card.index();
+    }
+
+    pub fn remove(&mut self, card: Card) {
+        self.0 &= !(1 << card.index());
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.0 & (1 << card.index()) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // The ranks held in one suit, bit 0 for a two up to bit 12 for an ace
+    pub fn suit_mask(&self, suit: Suit) -> u32 {
+        ((self.0 >> (13 * suit as u64)) & SUIT_BITS) as u32
+    }
+
+    // The ranks held in any suit
+    pub fn rank_mask(&self) -> u32 {
+        SUITS
+            .iter()
+            .fold(0, |mask, &suit| mask | self.suit_mask(suit))
+    }
+
+    // Cards in deck order: clubs two first, spades ace last
+    pub fn iter(&self) -> impl Iterator<Item = Card> + Clone {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let index = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            Some(Card::from_index(index))
+        })
+    }
+
+    pub fn to_vec(&self) -> Vec<Card> {
+        self.iter().collect()
+    }
+}
+
+impl Card {
+    // Inverse of `index`
+    pub fn from_index(index: usize) -> Card {
+        Card::new(RANKS[index % 13], SUITS[index / 13])
+    }
+}
+
+impl From<Card> for CardSet {
+    fn from(card: Card) -> CardSet {
+        CardSet(1 << card.index())
+    }
+}
+
+impl From<&[Card]> for CardSet {
+    fn from(cards: &[Card]) -> CardSet {
+        cards.iter().copied().collect()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(cards: I) -> CardSet {
+        let mut set = CardSet::new();
+        for card in cards {
+            set.insert(card);
+        }
+        set
+    }
+}
+
+impl BitOr for CardSet {
+    type Output = CardSet;
+
+    fn bitor(self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+}
+
+impl BitAnd for CardSet {
+    type Output = CardSet;
+
+    fn bitand(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+}
+
+// The cards of the deck not in the set
+impl Not for CardSet {
+    type Output = CardSet;
+
+    fn not(self) -> CardSet {
+        CardSet(!self.0 & CardSet::FULL.0)
+    }
+}
+
+impl fmt::Debug for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CardSet({})", self)
+    }
+}
+
+impl fmt::Display for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cards: Vec<String> = self.iter().map(|card| card.to_string()).collect();
+        write!(f, "{}", cards.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_cards;
+    use crate::Rank;
+
+    fn set(text: &str) -> CardSet {
+        CardSet::from(&parse_cards(text).unwrap()[..])
+    }
+
+    #[test]
+    fn suit_and_rank_masks() {
+        let cards = set("2c Ac 5d Kh Ks");
+        assert_eq!(cards.suit_mask(Suit::Clubs), 1 << 12 | 1);
+        assert_eq!(cards.suit_mask(Suit::Diamonds), 1 << 3);
+        assert_eq!(cards.suit_mask(Suit::Hearts), 1 << 11);
+        assert_eq!(cards.suit_mask(Suit::Spades), 1 << 11);
+        assert_eq!(cards.rank_mask(), 1 << 12 | 1 << 11 | 1 << 3 | 1);
+        assert_eq!(CardSet::FULL.suit_mask(Suit::Spades), 0x1fff);
+    }
+
+    #[test]
+    fn iterates_in_deck_order() {
+        let cards = set("As 2c Kd 2s Th");
+        assert_eq!(cards.to_string(), "2c Kd Th 2s As");
+        let deck: Vec<usize> = crate::Deck::new().cards().iter().map(Card::index).collect();
+        let full: Vec<usize> = CardSet::FULL.iter().map(|card| card.index()).collect();
+        assert_eq!(full, deck);
+        for index in 0..52 {
+            assert_eq!(Card::from_index(index).index(), index);
+        }
+    }
+
+    #[test]
+    fn insert_remove_and_len() {
+        let mut cards = CardSet::new();
+        assert!(cards.is_empty());
+        let ace = Card::new(Rank::Ace, Suit::Spades);
+        cards.insert(ace);
+        cards.insert(ace);
+        assert_eq!(cards.len(), 1);
+        assert!(cards.contains(ace));
+        cards.remove(ace);
+        assert!(cards.is_empty() && !cards.contains(ace));
+        assert_eq!(CardSet::FULL.len(), 52);
+        assert_eq!(set("Ah Kh Qh").len(), 3);
+    }
+
+    #[test]
+    fn set_operators() {
+        let (a, b) = (set("Ah Kh Qh"), set("Qh Jh 2c"));
+        assert_eq!(a | b, set("Ah Kh Qh Jh 2c"));
+        assert_eq!(a & b, set("Qh"));
+        assert_eq!((!a).len(), 49);
+        assert_eq!(!a & a, CardSet::EMPTY);
+        assert_eq!(!a | a, CardSet::FULL);
+        assert_eq!(!CardSet::FULL, CardSet::EMPTY);
+    }
+}
@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+
+// Limits on how long a computation may run. Work is done in chunks and the budget is
+// checked between chunks, so a run may overshoot the time limit by at most one chunk.
+#[derive(Debug, Clone)]
+pub struct Budget {
+    max_duration: Option<Duration>,
+    max_iterations: Option<usize>,
+    start: Instant,
+}
+
+impl Budget {
+    pub fn new(max_seconds: Option<f64>, max_iterations: Option<usize>) -> Budget {
+        Budget {
+            max_duration: max_seconds.map(Duration::from_secs_f64),
+            max_iterations,
+            start: Instant::now(),
+        }
+    }
+
+    // Size of the next chunk to run, or 0 once the work or the budget is used up
+    pub fn next_chunk(&self, done: usize, requested: usize, chunk_size: usize) -> usize {
+        let target = self
+            .max_iterations
+            .map_or(requested, |max| max.min(requested));
+        let out_of_time = self
+            .max_duration
+            .is_some_and(|max| self.start.elapsed() >= max);
+        if out_of_time {
+            return 0;
+        }
+        chunk_size.min(target.saturating_sub(done))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
@@ -0,0 +1,134 @@
+use crate::folding::FoldModel;
+use crate::query::QueryCounts;
+use crate::variant::Variant;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// On-disk cache of query results. Entries are keyed by a stable hash of the canonical
+// query text together with the simulation parameters it was computed with, the seed
+// included when one was given.
+pub struct QueryCache {
+    path: Option<PathBuf>,
+    entries: HashMap<u64, (String, QueryCounts)>,
+}
+
+// FNV-1a; std's hasher is not guaranteed stable across releases, which matters on disk
+fn stable_hash(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn default_path() -> Option<PathBuf> {
+    let dir = if let Some(dir) = env::var_os("POKERSIM_CACHE_DIR") {
+        PathBuf::from(dir)
+    } else if let Some(dir) = env::var_os("XDG_CACHE_HOME") {
+        PathBuf::from(dir).join("pokersim")
+    } else {
+        PathBuf::from(env::var_os("HOME")?)
+            .join(".cache")
+            .join("pokersim")
+    };
+    Some(dir.join("queries.txt"))
+}
+
+impl QueryCache {
+    // A cache that never hits and never writes
+    pub fn disabled() -> QueryCache {
+        QueryCache {
+            path: None,
+            entries: HashMap::new(),
+        }
+    }
+
+    // Load the default cache file; a missing or unreadable file just means an empty cache
+    pub fn open() -> QueryCache {
+        let path = default_path();
+        let mut entries = HashMap::new();
+        if let Some(contents) = path.as_ref().and_then(|path| fs::read_to_string(path).ok()) {
+            for line in contents.lines() {
+                let fields: Vec<&str> = line.splitn(4, '\t').collect();
+                if let [hash, conditioned, hits, key] = fields[..] {
+                    if let (Ok(hash), Ok(conditioned), Ok(hits)) = (
+                        u64::from_str_radix(hash, 16),
+                        conditioned.parse(),
+                        hits.parse(),
+                    ) {
+                        let counts = QueryCounts { conditioned, hits };
+                        entries.insert(hash, (key.to_string(), counts));
+                    }
+                }
+            }
+        }
+        QueryCache { path, entries }
+    }
+
+    pub fn key(
+        canonical_query: &str,
+        num_players: usize,
+        num_games: usize,
+        fold_model: Option<&FoldModel>,
+        variant: Variant,
+        max_draw: usize,
+        seed: Option<u64>,
+    ) -> String {
+        let mut key = format!(
+            "{} players={} games={}",
+            canonical_query, num_players, num_games
+        );
+        // Showdown-only keys stay as they were, so existing entries remain valid
+        match fold_model.map(|model| (model, model.table())) {
+            Some((_, Some(table))) => key.push_str(&format!(" table={}", table)),
+            Some((model, None)) => key.push_str(&format!(" vpip={}", model.vpip(0))),
+            None => {}
+        }
+        if variant != Variant::Holdem {
+            key.push_str(&format!(" variant={}", variant));
+        }
+        if variant.draw_cards() > 0 {
+            key.push_str(&format!(" max_draw={}", max_draw));
+        }
+        // A given seed pins the results down; unseeded runs are all equally good answers
+        if let Some(seed) = seed {
+            key.push_str(&format!(" seed={}", seed));
+        }
+        key
+    }
+
+    pub fn get(&self, key: &str) -> Option<QueryCounts> {
+        // Compare the full key too, so a hash collision is a miss rather than a wrong answer
+        match self.entries.get(&stable_hash(key)) {
+            Some((stored_key, counts)) if stored_key == key => Some(*counts),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, key: String, counts: QueryCounts) {
+        self.entries.insert(stable_hash(&key), (key, counts));
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut lines: Vec<String> = self
+            .entries
+            .iter()
+            .map(|(hash, (key, counts))| {
+                format!(
+                    "{:016x}\t{}\t{}\t{}\n",
+                    hash, counts.conditioned, counts.hits, key
+                )
+            })
+            .collect();
+        lines.sort();
+        fs::write(path, lines.concat())
+    }
+}
@@ -0,0 +1,200 @@
+use crate::lookup::evaluate_seven;
+use crate::notation::{rank_to_char, RANKS};
+use crate::rng::{task_rng, Purpose};
+use crate::simulator::{fold_batches, SeatResults};
+use crate::variant::Variant;
+use crate::{Card, Deck, GameResult, Rank, Suit};
+use std::fmt;
+
+// One of the 169 starting hand classes, e.g. AKs, AKo or QQ. Pairs are never suited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandClass {
+    pub high: Rank,
+    pub low: Rank,
+    pub suited: bool,
+}
+
+// The ranks of a class written as "AKs", "AKo", "QQ" or "AK", highest first, and whether it
+// is suited: None for pairs, and for "AK", which stands for both. The one parser of class
+// notation, shared by ranges and the combo counter.
+pub fn parse_class(text: &str) -> Result<(Rank, Rank, Option<bool>), String> {
+    let invalid = || format!("invalid hand class: {}", text);
+    let chars: Vec<char> = text.chars().collect();
+    let (a, b, suited) = match chars[..] {
+        [a, b] => (a, b, None),
+        [a, b, 's'] => (a, b, Some(true)),
+        [a, b, 'o'] => (a, b, Some(false)),
+        _ => return Err(invalid()),
+    };
+    let (a, b) = match (a.to_string().parse::<Rank>(), b.to_string().parse::<Rank>()) {
+        (Ok(a), Ok(b)) => (a, b),
+        _ => return Err(invalid()),
+    };
+    // Pairs are never suited or offsuit
+    if a == b && suited.is_some() {
+        return Err(invalid());
+    }
+    Ok((a.max(b), a.min(b), suited))
+}
+
+impl HandClass {
+    // Every class, strongest ranks first: AA, AKs, AKo, AQs, ... 22
+    pub fn all() -> Vec<HandClass> {
+        let mut classes = Vec::with_capacity(169);
+        for &high in RANKS.iter().rev() {
+            for &low in RANKS.iter().rev().filter(|&&low| low <= high) {
+                if high == low {
+                    classes.push(HandClass {
+                        high,
+                        low,
+                        suited: false,
+                    });
+                } else {
+                    for suited in [true, false] {
+                        classes.push(HandClass { high, low, suited });
+                    }
+                }
+            }
+        }
+        classes
+    }
+
+    // A class written as "AKs", "AKo" or "QQ"; "AK" is ambiguous here
+    pub fn parse(text: &str) -> Result<HandClass, String> {
+        match parse_class(text)? {
+            (high, low, Some(suited)) => Ok(HandClass { high, low, suited }),
+            (high, low, None) if high == low => Ok(HandClass {
+                high,
+                low,
+                suited: false,
+            }),
+            _ => Err(format!(
+                "invalid hand class: {}; write {}s or {}o",
+                text, text, text
+            )),
+        }
+    }
+
+    // The class in a 13x13 grid with aces in the first row and column: pairs on the
+    // diagonal, suited hands above it and offsuit hands below, e.g. AKs in row 0, column 1
+    pub fn in_grid(row: usize, column: usize) -> HandClass {
+        let rank = |index: usize| RANKS[RANKS.len() - 1 - index];
+        let (first, second) = (rank(row), rank(column));
+        HandClass {
+            high: first.max(second),
+            low: first.min(second),
+            suited: row < column,
+        }
+    }
+
+    // Concrete hole cards of this class; equity does not depend on which suits are used
+    pub fn representative(&self) -> [Card; 2] {
+        let low_suit = if self.suited {
+            Suit::Spades
+        } else {
+            Suit::Hearts
+        };
+        [
+            Card::new(self.high, Suit::Spades),
+            Card::new(self.low, low_suit),
+        ]
+    }
+}
+
+impl fmt::Display for HandClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", rank_to_char(self.high), rank_to_char(self.low))?;
+        if self.high != self.low {
+            write!(f, "{}", if self.suited { 's' } else { 'o' })?;
+        }
+        Ok(())
+    }
+}
+
+// Pot equity of `class` against `opponents` random hands that all see the showdown,
+// estimated over `trials` deals reproducible from `seed`
+pub fn equity_vs_callers(class: HandClass, opponents: usize, trials: usize, seed: u64) -> f64 {
+    results_vs_callers(class, opponents, trials, seed).equity() / trials as f64
+}
+
+// Wins, ties and pot equity of `class` against `opponents` random hands that all see the
+// showdown over `trials` deals reproducible from `seed`. Every class is played with one
+// representative hand, since the suits only matter up to relabelling.
+pub fn results_vs_callers(
+    class: HandClass,
+    opponents: usize,
+    trials: usize,
+    seed: u64,
+) -> SeatResults {
+    let hero = class.representative();
+    fold_batches(
+        0..trials,
+        SeatResults::default,
+        |mut total, games| {
+            let mut rng = task_rng(seed, Purpose::Deal, games.start as u64);
+            for _ in games {
+                let mut deck = Deck::new();
+                for card in &hero {
+                    deck.remove(card);
+                }
+                deck.shuffle(&mut rng);
+
+                let mut hole_cards = vec![hero.to_vec()];
+                for _ in 0..opponents {
+                    hole_cards.push(vec![deck.deal().unwrap(), deck.deal().unwrap()]);
+                }
+                let board: Vec<Card> = (0..5).map(|_| deck.deal().unwrap()).collect();
+                let hand_ranks = hole_cards
+                    .iter()
+                    .map(|hole_cards| {
+                        let mut cards = [board[0]; 7];
+                        cards[..2].copy_from_slice(hole_cards);
+                        cards[2..].copy_from_slice(&board);
+                        evaluate_seven(&cards).to_hand_rank()
+                    })
+                    .collect();
+
+                let folded = vec![false; opponents + 1];
+                let result = GameResult::from_showdown(
+                    Variant::Holdem,
+                    hole_cards,
+                    board,
+                    hand_ranks,
+                    folded,
+                );
+                total.record(&result, 0);
+            }
+            total
+        },
+        |mut a, b| {
+            a.merge(&b);
+            a
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::Range;
+
+    #[test]
+    fn class_notation() {
+        use Rank::*;
+        assert_eq!(parse_class("AKs"), Ok((Ace, King, Some(true))));
+        assert_eq!(parse_class("ka"), Ok((Ace, King, None)));
+        assert_eq!(parse_class("T9o"), Ok((Ten, Nine, Some(false))));
+        assert_eq!(parse_class("QQ"), Ok((Queen, Queen, None)));
+        for bad in ["QQs", "AKx", "A", "AKso", "1K", ""] {
+            assert_eq!(
+                parse_class(bad),
+                Err(format!("invalid hand class: {}", bad))
+            );
+        }
+        // Classes proper must say suited or offsuit; ranges and combo counts may not
+        assert!(HandClass::parse("AK").is_err());
+        assert!(!HandClass::parse("QQ").unwrap().suited);
+        assert_eq!(Range::parse("AK").unwrap().combos().len(), 16);
+        assert!(Range::parse("QQs").is_err());
+    }
+}
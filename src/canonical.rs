@@ -0,0 +1,204 @@
+use crate::bitboard::CardSet;
+use crate::notation::SUITS;
+use crate::{Card, Suit};
+use itertools::Itertools;
+use std::sync::OnceLock;
+
+// Suit isomorphism: relabelling the suits never changes how hands play, so AsKs and AhKh
+// are the same hand and Ah 7c 2d the same flop as As 7h 2c. Every class of such card sets
+// has one canonical representative, the member whose suits make the smallest bit mask,
+// and the classes of up to five cards are numbered densely for caching and tables: 169
+// starting hands, 1,755 flops, 16,432 turns and 134,459 full boards.
+
+// Cards of the largest sets numbered by `class_id`
+pub const MAX_CLASS_CARDS: usize = 5;
+
+// A relabelling of the four suits: clubs become the first suit, diamonds the second and
+// so on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SuitPermutation([Suit; 4]);
+
+impl SuitPermutation {
+    pub fn identity() -> SuitPermutation {
+        SuitPermutation(SUITS)
+    }
+
+    // All 24 relabellings, the identity first
+    pub fn all() -> &'static [SuitPermutation] {
+        static ALL: OnceLock<Vec<SuitPermutation>> = OnceLock::new();
+        ALL.get_or_init(|| {
+            SUITS
+                .into_iter()
+                .permutations(4)
+                .map(|suits| SuitPermutation([suits[0], suits[1], suits[2], suits[3]]))
+                .collect()
+        })
+    }
+
+    pub fn suit(&self, suit: Suit) -> Suit {
+        self.0[suit as usize]
+    }
+
+    pub fn card(&self, card: Card) -> Card {
+        Card::new(card.rank, self.suit(card.suit))
+    }
+
+    pub fn apply(&self, cards: CardSet) -> CardSet {
+        // Each suit is a lane of 13 bits, moved as a whole
+        SUITS.iter().fold(CardSet::new(), |set, &suit| {
+            let lane = (cards.suit_mask(suit) as u64) << (13 * self.suit(suit) as u64);
+            set | CardSet(lane)
+        })
+    }
+
+    // The relabelling that undoes this one
+    pub fn inverse(&self) -> SuitPermutation {
+        let mut suits = SUITS;
+        for suit in SUITS {
+            suits[self.suit(suit) as usize] = suit;
+        }
+        SuitPermutation(suits)
+    }
+}
+
+// The canonical form of several groups of cards relabelled together, e.g. hole cards and a
+// flop, so AhKh on Qh 7h 2c stays a hand with four hearts. Returns the relabelled groups
+// and the relabelling that made them; its inverse maps them back.
+pub fn canonicalize(groups: &[CardSet]) -> (Vec<CardSet>, SuitPermutation) {
+    SuitPermutation::all()
+        .iter()
+        .map(|permutation| {
+            let relabelled: Vec<CardSet> = groups
+                .iter()
+                .map(|&cards| permutation.apply(cards))
+                .collect();
+            (relabelled, *permutation)
+        })
+        .min_by_key(|(relabelled, _)| relabelled.iter().map(|cards| cards.0).collect::<Vec<_>>())
+        .unwrap()
+}
+
+// The canonical representative of a single group of cards
+pub fn canonical(cards: CardSet) -> CardSet {
+    SuitPermutation::all()
+        .iter()
+        .map(|permutation| permutation.apply(cards))
+        .min_by_key(|cards| cards.0)
+        .unwrap()
+}
+
+pub fn is_canonical(cards: CardSet) -> bool {
+    canonical(cards) == cards
+}
+
+// Every card set in the class of `cards`, in bit mask order
+pub fn members(cards: CardSet) -> Vec<CardSet> {
+    SuitPermutation::all()
+        .iter()
+        .map(|permutation| permutation.apply(cards))
+        .sorted_by_key(|cards| cards.0)
+        .dedup()
+        .collect()
+}
+
+// Canonical sets of every size up to MAX_CLASS_CARDS, sorted by bit mask, so a class's
+// number is its position. Each size is built the first time it is needed.
+fn classes(size: usize) -> &'static [CardSet] {
+    assert!(
+        size <= MAX_CLASS_CARDS,
+        "classes are numbered for up to {} cards, not {}",
+        MAX_CLASS_CARDS,
+        size
+    );
+    static CLASSES: [OnceLock<Vec<CardSet>>; MAX_CLASS_CARDS + 1] =
+        [const { OnceLock::new() }; MAX_CLASS_CARDS + 1];
+    CLASSES[size].get_or_init(|| {
+        CardSet::FULL
+            .iter()
+            .combinations(size)
+            .map(CardSet::from_iter)
+            .filter(|&cards| is_canonical(cards))
+            .sorted_by_key(|cards| cards.0)
+            .collect()
+    })
+}
+
+// How many classes of `size` cards there are, e.g. 169 for two
+pub fn class_count(size: usize) -> usize {
+    classes(size).len()
+}
+
+// The number of the class of `cards`, from 0 to `class_count` of their size
+pub fn class_id(cards: CardSet) -> usize {
+    let canonical = canonical(cards);
+    classes(cards.len())
+        .binary_search_by_key(&canonical.0, |cards| cards.0)
+        .unwrap()
+}
+
+// The canonical representative of class `id` of `size` cards, the inverse of `class_id`
+pub fn from_class_id(size: usize, id: usize) -> Option<CardSet> {
+    classes(size).get(id).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_cards;
+
+    fn cards(text: &str) -> CardSet {
+        CardSet::from(&parse_cards(text).unwrap()[..])
+    }
+
+    #[test]
+    fn starting_hands_collapse_to_169_classes() {
+        let hands: Vec<CardSet> = CardSet::FULL
+            .iter()
+            .combinations(2)
+            .map(CardSet::from_iter)
+            .collect();
+        assert_eq!(hands.len(), 1326);
+        let classes = hands.iter().map(|&hand| canonical(hand)).unique().count();
+        assert_eq!(classes, 169);
+        assert_eq!(class_count(2), 169);
+        // 13 pairs of 6 combos, 78 suited classes of 4 and 78 offsuit classes of 12
+        let sizes = hands
+            .iter()
+            .map(|&hand| class_id(hand))
+            .counts()
+            .into_values()
+            .counts();
+        assert_eq!(sizes[&6], 13);
+        assert_eq!(sizes[&4], 78);
+        assert_eq!(sizes[&12], 78);
+    }
+
+    #[test]
+    fn suit_permutations_share_a_key() {
+        let hand = cards("As Ks");
+        for permutation in SuitPermutation::all() {
+            let relabelled = permutation.apply(hand);
+            assert_eq!(canonical(relabelled), canonical(hand));
+            assert_eq!(class_id(relabelled), class_id(hand));
+            assert_eq!(permutation.inverse().apply(relabelled), hand);
+        }
+        assert_eq!(SuitPermutation::all().len(), 24);
+        assert_eq!(class_id(cards("Ah Kd")), class_id(cards("Ac Ks")));
+        assert_ne!(class_id(cards("Ah Kh")), class_id(cards("Ah Kd")));
+        assert_eq!(members(cards("Qc Qd")).len(), 6);
+        let id = class_id(cards("7h 2c"));
+        assert!(is_canonical(from_class_id(2, id).unwrap()));
+        assert_eq!(class_id(from_class_id(2, id).unwrap()), id);
+    }
+
+    #[test]
+    fn groups_are_relabelled_together() {
+        let hole = cards("Ah Kh");
+        let (suited_flop, _) = canonicalize(&[hole, cards("Qh 7h 2c")]);
+        let (offsuit_flop, _) = canonicalize(&[hole, cards("Qc 7c 2h")]);
+        assert_ne!(suited_flop, offsuit_flop);
+        let (same, permutation) = canonicalize(&[cards("As Ks"), cards("Qs 7s 2d")]);
+        assert_eq!(same, suited_flop);
+        assert_eq!(permutation.inverse().apply(same[0]), cards("As Ks"));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::sketch::Histogram;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// Per-chunk performance time series: throughput, allocations and per-game latency,
+// printed to stderr and/or written as CSV
+pub struct ChunkLog {
+    print: bool,
+    csv: Option<BufWriter<File>>,
+}
+
+pub struct ChunkStats<'a> {
+    pub index: usize,
+    pub games: usize,
+    pub seconds: f64,
+    pub allocations: Option<u64>,
+    pub game_times: &'a Histogram,
+}
+
+impl ChunkLog {
+    pub fn new(print: bool, csv_path: Option<&str>) -> io::Result<ChunkLog> {
+        let csv = match csv_path {
+            Some(path) => {
+                let mut csv = BufWriter::new(File::create(path)?);
+                writeln!(
+                    csv,
+                    "chunk,games,seconds,games_per_second,allocations,p50_us,p90_us,p99_us"
+                )?;
+                Some(csv)
+            }
+            None => None,
+        };
+        Ok(ChunkLog { print, csv })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.print || self.csv.is_some()
+    }
+
+    pub fn log(&mut self, stats: &ChunkStats) -> io::Result<()> {
+        let games_per_second = stats.games as f64 / stats.seconds;
+        let micros = |q: f64| stats.game_times.quantile(q) as f64 / 1000.0;
+        let allocations = stats
+            .allocations
+            .map_or(String::new(), |count| count.to_string());
+
+        if self.print {
+            eprintln!(
+                "chunk {}: {} games in {:.3}s ({:.0} games/s), allocations {}, p50 {:.1}\u{b5}s, p90 {:.1}\u{b5}s, p99 {:.1}\u{b5}s",
+                stats.index,
+                stats.games,
+                stats.seconds,
+                games_per_second,
+                if allocations.is_empty() { "n/a" } else { &allocations },
+                micros(0.5),
+                micros(0.9),
+                micros(0.99)
+            );
+        }
+        if let Some(csv) = &mut self.csv {
+            writeln!(
+                csv,
+                "{},{},{:.6},{:.1},{},{:.2},{:.2},{:.2}",
+                stats.index,
+                stats.games,
+                stats.seconds,
+                games_per_second,
+                allocations,
+                micros(0.5),
+                micros(0.9),
+                micros(0.99)
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        match &mut self.csv {
+            Some(csv) => csv.flush(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{Card, Rank, Suit};
+
+// Exact counting helpers: binomial coefficients, hypergeometric probabilities, outs
+// to odds, and starting-hand combos left after card removal
+
+pub fn choose(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    // Each partial product is itself a binomial coefficient, so the division is exact
+    (0..k).fold(1u128, |acc, i| acc * (n - i) as u128 / (i + 1) as u128) as u64
+}
+
+// The `rank`-th k-subset of 0..n in lexicographic order, so that every subset can be
+// visited by index, e.g. split across threads
+pub fn unrank_combination(mut rank: u64, n: u64, k: u64) -> Vec<usize> {
+    let mut subset = Vec::with_capacity(k as usize);
+    let mut next = 0;
+    for slot in 0..k {
+        loop {
+            // Subsets starting with `next` at this slot
+            let count = choose(n - next - 1, k - slot - 1);
+            if rank < count {
+                break;
+            }
+            rank -= count;
+            next += 1;
+        }
+        subset.push(next as usize);
+        next += 1;
+    }
+    subset
+}
+
+// Probability of exactly `hits` successes when drawing `draws` cards without
+// replacement from `population` cards of which `successes` are successes
+pub fn hypergeometric(population: u64, successes: u64, draws: u64, hits: u64) -> f64 {
+    if hits > draws || hits > successes || draws - hits > population - successes {
+        return 0.0;
+    }
+    choose(successes, hits) as f64 * choose(population - successes, draws - hits) as f64
+        / choose(population, draws) as f64
+}
+
+// Probability of hitting at least one of `outs` among `unseen` cards with `draws` to come
+pub fn prob_at_least_one(outs: u64, unseen: u64, draws: u64) -> f64 {
+    1.0 - hypergeometric(unseen, outs, draws, 0)
+}
+
+// Odds against an event as "x to 1"
+pub fn odds_against(probability: f64) -> f64 {
+    (1.0 - probability) / probability
+}
+
+// Combos of a starting hand class (e.g. AKs, AKo, QQ) still possible once the `dead`
+// cards are known. `suited` is ignored for pairs; None counts suited and offsuit.
+pub fn hand_class_combos(high: Rank, low: Rank, suited: Option<bool>, dead: &[Card]) -> u64 {
+    const SUITS: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+    let live = |rank: Rank, suit: Suit| {
+        !dead
+            .iter()
+            .any(|card| card.rank == rank && card.suit == suit)
+    };
+
+    if high == low {
+        let live_suits = SUITS.iter().filter(|&&suit| live(high, suit)).count() as u64;
+        return choose(live_suits, 2);
+    }
+
+    let mut combos = 0;
+    for &high_suit in &SUITS {
+        for &low_suit in &SUITS {
+            let is_suited = high_suit == low_suit;
+            if suited.is_some_and(|suited| suited != is_suited) {
+                continue;
+            }
+            if live(high, high_suit) && live(low, low_suit) {
+                combos += 1;
+            }
+        }
+    }
+    combos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn binomials() {
+        assert_eq!(choose(52, 5), 2_598_960);
+        assert_eq!(choose(52, 2), 1326);
+        assert_eq!(choose(5, 0), 1);
+        assert_eq!(choose(3, 5), 0);
+        assert_eq!(choose(60, 30), 118_264_581_564_861_424);
+    }
+
+    #[test]
+    fn unrank_visits_every_subset_in_order() {
+        for (n, k) in [(5, 0), (5, 2), (6, 3), (7, 7)] {
+            let subsets: Vec<Vec<usize>> = (0..choose(n, k))
+                .map(|rank| unrank_combination(rank, n, k))
+                .collect();
+            let expected: Vec<Vec<usize>> = (0..n as usize).combinations(k as usize).collect();
+            assert_eq!(subsets, expected);
+        }
+    }
+
+    #[test]
+    fn class_combos_after_card_removal() {
+        let ace = Card::new(Rank::Ace, Suit::Spades);
+        assert_eq!(hand_class_combos(Rank::Ace, Rank::Ace, None, &[]), 6);
+        assert_eq!(hand_class_combos(Rank::Ace, Rank::Ace, None, &[ace]), 3);
+        assert_eq!(hand_class_combos(Rank::Ace, Rank::King, Some(true), &[]), 4);
+        assert_eq!(
+            hand_class_combos(Rank::Ace, Rank::King, Some(false), &[]),
+            12
+        );
+        assert_eq!(hand_class_combos(Rank::Ace, Rank::King, None, &[ace]), 12);
+        assert_eq!(
+            hand_class_combos(Rank::Ace, Rank::King, Some(true), &[ace]),
+            3
+        );
+    }
+
+    #[test]
+    fn draws() {
+        // Nine flush outs twice from 47 unseen cards
+        let hit = prob_at_least_one(9, 47, 2);
+        assert!((hit - 0.3497).abs() < 1e-4);
+        assert!((odds_against(0.25) - 3.0).abs() < 1e-12);
+        assert_eq!(hypergeometric(10, 3, 2, 3), 0.0);
+    }
+}
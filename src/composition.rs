@@ -0,0 +1,126 @@
+use crate::bitboard::CardSet;
+use crate::combinatorics::choose;
+use crate::notation::{RANKS, SUITS};
+use crate::{Card, Rank, Suit};
+use itertools::Itertools;
+
+// What is left in the deck once the visible and dead cards are taken out: how many cards
+// of each suit and rank, and which starting hands can still be dealt from them
+#[derive(Debug, Clone, Copy)]
+pub struct DeckComposition {
+    remaining: CardSet,
+}
+
+impl DeckComposition {
+    // A 52-card deck without the `seen` cards
+    pub fn new(seen: CardSet) -> DeckComposition {
+        DeckComposition::of_deck(CardSet::FULL, seen)
+    }
+
+    // `deck`, e.g. a Short Deck pack, without the `seen` cards
+    pub fn of_deck(deck: CardSet, seen: CardSet) -> DeckComposition {
+        DeckComposition {
+            remaining: deck & !seen,
+        }
+    }
+
+    pub fn remaining(&self) -> CardSet {
+        self.remaining
+    }
+
+    pub fn len(&self) -> usize {
+        self.remaining.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining.is_empty()
+    }
+
+    pub fn suit_count(&self, suit: Suit) -> usize {
+        self.remaining.suit_mask(suit).count_ones() as usize
+    }
+
+    pub fn rank_count(&self, rank: Rank) -> usize {
+        SUITS
+            .iter()
+            .filter(|&&suit| self.remaining.contains(Card::new(rank, suit)))
+            .count()
+    }
+
+    // Cards left that meet `is_out`, e.g. the outs to a draw
+    pub fn count(&self, is_out: impl Fn(Card) -> bool) -> usize {
+        self.remaining.iter().filter(|&card| is_out(card)).count()
+    }
+
+    // Two-card hands that can still be dealt
+    pub fn hand_combos(&self) -> usize {
+        choose(self.len() as u64, 2) as usize
+    }
+
+    // Pocket pairs of `rank` that can still be dealt
+    pub fn pair_combos(&self, rank: Rank) -> usize {
+        choose(self.rank_count(rank) as u64, 2) as usize
+    }
+
+    // Pocket pairs of any rank that can still be dealt
+    pub fn all_pair_combos(&self) -> usize {
+        RANKS.iter().map(|&rank| self.pair_combos(rank)).sum()
+    }
+
+    // Suited two-card hands that can still be dealt
+    pub fn suited_combos(&self) -> usize {
+        SUITS
+            .iter()
+            .map(|&suit| choose(self.suit_count(suit) as u64, 2) as usize)
+            .sum()
+    }
+
+    // Every two-card hand that can still be dealt, in deck order
+    pub fn hands(&self) -> Vec<[Card; 2]> {
+        self.remaining
+            .iter()
+            .tuple_combinations()
+            .map(|(a, b)| [a, b])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_cards;
+
+    fn without(text: &str) -> DeckComposition {
+        DeckComposition::new(CardSet::from(&parse_cards(text).unwrap()[..]))
+    }
+
+    #[test]
+    fn full_deck() {
+        let deck = DeckComposition::new(CardSet::EMPTY);
+        assert_eq!(deck.len(), 52);
+        assert_eq!(deck.hand_combos(), 1326);
+        assert_eq!(deck.hands().len(), 1326);
+        assert_eq!(deck.pair_combos(Rank::Ace), 6);
+        assert_eq!(deck.all_pair_combos(), 78);
+        assert_eq!(deck.suited_combos(), 312);
+    }
+
+    #[test]
+    fn seen_cards_come_out() {
+        let deck = without("As Kh Qh");
+        assert_eq!(deck.len(), 49);
+        assert_eq!(deck.pair_combos(Rank::Ace), 3);
+        assert_eq!(deck.rank_count(Rank::Ace), 3);
+        assert_eq!(deck.suit_count(Suit::Hearts), 11);
+        assert_eq!(deck.hands().len(), deck.hand_combos());
+        assert!(deck
+            .hands()
+            .iter()
+            .all(|hand| hand.iter().all(|&card| deck.remaining().contains(card))));
+        // Hearts left for a flush draw
+        assert_eq!(deck.count(|card| card.suit == Suit::Hearts), 11);
+        let short =
+            DeckComposition::of_deck(CardSet::from(crate::Deck::short().cards()), CardSet::EMPTY);
+        assert_eq!(short.hand_combos(), 630);
+    }
+}
@@ -0,0 +1,194 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// User settings from $POKERSIM_CONFIG, or else config.toml under $XDG_CONFIG_HOME/pokersim
+// or ~/.config/pokersim. Only flat TOML is understood: `key = value` lines, where keys
+// are long flag names and settings for a subcommand go under its `[name]` header.
+//
+//     threads = 4
+//     vpip = 0.25
+//
+//     [equity]
+//     trials = 1_000_000
+#[derive(Debug, Default)]
+pub struct Config {
+    path: Option<PathBuf>,
+    // Section ("" for the top level), key, value and line number, in file order
+    settings: Vec<(String, String, String, usize)>,
+}
+
+fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("POKERSIM_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let dir = if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        PathBuf::from(dir)
+    } else {
+        PathBuf::from(env::var_os("HOME")?).join(".config")
+    };
+    Some(dir.join("pokersim").join("config.toml"))
+}
+
+impl Config {
+    // Load the default config file; a missing file means no settings
+    pub fn load() -> Result<Config, String> {
+        let path = match default_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        };
+        let contents =
+            fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut config = Config::parse(&contents)
+            .map_err(|(line, err)| format!("{}:{}: {}", path.display(), line, err))?;
+        config.path = Some(path);
+        Ok(config)
+    }
+
+    // Parse config text, failing with the line number of the first bad line
+    pub fn parse(text: &str) -> Result<Config, (usize, String)> {
+        let mut settings = Vec::new();
+        let mut section = String::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or((number + 1, "expected `key = value`".to_string()))?;
+            // TOML style snake_case keys name the same flags as kebab-case ones
+            let key = key.trim().replace('_', "-");
+            let value = value.trim();
+            let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(quoted) => quoted.to_string(),
+                // Digit separators as in 1_000_000
+                None => value.replace('_', ""),
+            };
+            if key.is_empty() || value.is_empty() {
+                return Err((number + 1, "expected `key = value`".to_string()));
+            }
+            settings.push((section.clone(), key, value, number + 1));
+        }
+        Ok(Config {
+            path: None,
+            settings,
+        })
+    }
+
+    // Where the settings were read from, if a file was found
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    // Every (section, key, value) in file order
+    pub fn settings(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.settings
+            .iter()
+            .map(|(section, key, value, _)| (section.as_str(), key.as_str(), value.as_str()))
+    }
+
+    // Where a setting came from, as in "config.toml:3", for error messages
+    pub fn location(&self, section: &str, key: &str) -> String {
+        let line = self
+            .settings
+            .iter()
+            .rev()
+            .find(|setting| setting.0 == section && setting.1 == key)
+            .map_or(0, |setting| setting.3);
+        let path = self
+            .path
+            .as_ref()
+            .map_or("config".to_string(), |path| path.display().to_string());
+        format!("{}:{}", path, line)
+    }
+}
+
+// Drop a trailing `# comment`, leaving any `#` inside a quoted string alone
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(text: &str) -> Vec<(String, String, String)> {
+        Config::parse(text)
+            .unwrap()
+            .settings()
+            .map(|(section, key, value)| (section.to_string(), key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn setting(section: &str, key: &str, value: &str) -> (String, String, String) {
+        (section.to_string(), key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn digit_separators_and_keys() {
+        assert_eq!(
+            settings("games = 1_000_000\nmax_seconds = 2.5\n"),
+            [
+                setting("", "games", "1000000"),
+                setting("", "max-seconds", "2.5")
+            ]
+        );
+        // Quoted values are taken as written
+        assert_eq!(settings("log = \"a_b\""), [setting("", "log", "a_b")]);
+    }
+
+    #[test]
+    fn comments_and_quoted_hashes() {
+        let text =
+            "# settings\nthreads = 4 # all of them\nquery = \"P(hero.win) # not a comment\"\n";
+        assert_eq!(
+            settings(text),
+            [
+                setting("", "threads", "4"),
+                setting("", "query", "P(hero.win) # not a comment"),
+            ]
+        );
+    }
+
+    #[test]
+    fn section_headers() {
+        let text = "vpip = 0.3\n\n[equity]\ntrials = 10_000\n[ grid ]\ndeals = 50\n";
+        assert_eq!(
+            settings(text),
+            [
+                setting("", "vpip", "0.3"),
+                setting("equity", "trials", "10000"),
+                setting("grid", "deals", "50"),
+            ]
+        );
+        let config = Config::parse(text).unwrap();
+        assert_eq!(config.location("equity", "trials"), "config:4");
+    }
+
+    #[test]
+    fn bad_lines() {
+        let error = |text: &str| Config::parse(text).unwrap_err();
+        assert_eq!(
+            error("threads = 4\nthreads\n"),
+            (2, "expected `key = value`".to_string())
+        );
+        assert_eq!(error("\n\n = 4"), (3, "expected `key = value`".to_string()));
+        assert_eq!(
+            error("threads = # none"),
+            (1, "expected `key = value`".to_string())
+        );
+    }
+}
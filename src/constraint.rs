@@ -0,0 +1,88 @@
+use crate::{Card, Rank, Suit};
+use std::fmt;
+
+// What is known about an opponent's otherwise unknown two hole cards, e.g. "has A",
+// "suited, no s" or "pair". Predicates are separated by commas or "and" and must all hold.
+#[derive(Debug, Clone)]
+pub struct HandConstraint {
+    predicates: Vec<Predicate>,
+    source: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Feature {
+    Rank(Rank),
+    Suit(Suit),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Predicate {
+    Has(Feature),
+    No(Feature),
+    Suited,
+    Offsuit,
+    Pair,
+}
+
+impl Feature {
+    fn parse(text: &str) -> Option<Feature> {
+        text.parse()
+            .map(Feature::Rank)
+            .or_else(|_| text.parse().map(Feature::Suit))
+            .ok()
+    }
+
+    fn matches(&self, card: &Card) -> bool {
+        match *self {
+            Feature::Rank(rank) => card.rank == rank,
+            Feature::Suit(suit) => card.suit == suit,
+        }
+    }
+}
+
+impl Predicate {
+    fn matches(&self, hole_cards: &[Card]) -> bool {
+        match self {
+            Predicate::Has(feature) => hole_cards.iter().any(|card| feature.matches(card)),
+            Predicate::No(feature) => !hole_cards.iter().any(|card| feature.matches(card)),
+            Predicate::Suited => hole_cards[0].suit == hole_cards[1].suit,
+            Predicate::Offsuit => hole_cards[0].suit != hole_cards[1].suit,
+            Predicate::Pair => hole_cards[0].rank == hole_cards[1].rank,
+        }
+    }
+}
+
+impl HandConstraint {
+    pub fn parse(text: &str) -> Result<HandConstraint, String> {
+        let mut predicates = Vec::new();
+        for term in text.split(',').flat_map(|part| part.split(" and ")) {
+            let words: Vec<&str> = term.split_whitespace().collect();
+            let invalid = || format!("unknown hand constraint: {}", term.trim());
+            let predicate = match words[..] {
+                ["suited"] => Predicate::Suited,
+                ["offsuit"] => Predicate::Offsuit,
+                ["pair"] => Predicate::Pair,
+                ["has", feature] => Predicate::Has(Feature::parse(feature).ok_or_else(invalid)?),
+                ["no", feature] => Predicate::No(Feature::parse(feature).ok_or_else(invalid)?),
+                _ => return Err(invalid()),
+            };
+            predicates.push(predicate);
+        }
+        Ok(HandConstraint {
+            predicates,
+            source: text.trim().to_string(),
+        })
+    }
+
+    pub fn matches(&self, hole_cards: &[Card]) -> bool {
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.matches(hole_cards))
+    }
+}
+
+impl fmt::Display for HandConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
@@ -0,0 +1,37 @@
+use crate::notation::cards_to_string;
+use crate::reference::reference_value;
+use crate::rng::{task_rng, Purpose};
+use crate::{Card, Deck};
+use std::cmp::Ordering;
+use std::io::{self, Write};
+
+// Write `count` random showdowns as CSV. Both hands share a five-card board, as at a
+// real Hold'em showdown, so kickers and split pots come up often. The result column is
+// 1 when hand_a wins, -1 when hand_b wins and 0 for a tie, per the reference evaluator.
+pub fn write_dataset<W: Write>(mut out: W, count: usize, seed: u64) -> io::Result<()> {
+    writeln!(out, "hand_a,hand_b,result")?;
+    let mut rng = task_rng(seed, Purpose::Deal, 0);
+    for _ in 0..count {
+        let mut deck = Deck::new();
+        deck.shuffle(&mut rng);
+        let board: Vec<Card> = (0..5).map(|_| deck.deal().unwrap()).collect();
+        let mut hand_a: Vec<Card> = (0..2).map(|_| deck.deal().unwrap()).collect();
+        let mut hand_b: Vec<Card> = (0..2).map(|_| deck.deal().unwrap()).collect();
+        hand_a.extend_from_slice(&board);
+        hand_b.extend_from_slice(&board);
+
+        let result = match reference_value(&hand_a).cmp(&reference_value(&hand_b)) {
+            Ordering::Greater => 1,
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+        };
+        writeln!(
+            out,
+            "{},{},{}",
+            cards_to_string(&hand_a),
+            cards_to_string(&hand_b),
+            result
+        )?;
+    }
+    out.flush()
+}
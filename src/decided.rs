@@ -0,0 +1,119 @@
+use crate::bitboard::CardSet;
+use crate::lookup::evaluate;
+use crate::notation::SUITS;
+use crate::Card;
+
+// Whether a Hold'em showdown is already decided before the board is complete: a player
+// whose hand is of a better category than anything any opponent could still make with the
+// cards to come wins every runout, so an enumeration can credit the whole branch without
+// dealing it. Categories are numbered as in filter::category_value.
+
+// Five-rank windows of every straight, wheel first, as rank masks (bit 0 for a two)
+const STRAIGHTS: [u32; 10] = [
+    0x100f, 0x1f, 0x3e, 0x7c, 0xf8, 0x1f0, 0x3e0, 0x7c0, 0xf80, 0x1f00,
+];
+
+// The best category `cards` can make once `to_come` more cards are drawn from `live`. It
+// may overstate what is possible, as draws that need the same card are not told apart, but
+// never understates it.
+pub fn best_reachable(cards: CardSet, live: CardSet, to_come: usize) -> u32 {
+    // Whether the `missing` ranks of a window are all among the `left` ones and few enough
+    // to draw
+    let drawable =
+        |missing: u32, left: u32| missing & !left == 0 && missing.count_ones() as usize <= to_come;
+
+    // Straight flushes, the royal first
+    for (category, windows) in [(9, &STRAIGHTS[9..]), (8, &STRAIGHTS[..9])] {
+        for suit in SUITS {
+            let (held, left) = (cards.suit_mask(suit), live.suit_mask(suit));
+            let reachable = windows.iter().any(|&window| drawable(window & !held, left));
+            if reachable {
+                return category;
+            }
+        }
+    }
+
+    // Cards of each rank held and still live
+    let count = |set: CardSet, rank: usize| {
+        SUITS
+            .iter()
+            .filter(|&&suit| set.suit_mask(suit) >> rank & 1 == 1)
+            .count()
+    };
+    let held: [usize; 13] = std::array::from_fn(|rank| count(cards, rank));
+    let left: [usize; 13] = std::array::from_fn(|rank| count(live, rank));
+    // Cards needed for `size` of a rank, if enough are live
+    let need = |rank: usize, size: usize| {
+        let missing = size.saturating_sub(held[rank]);
+        (missing <= left[rank]).then_some(missing)
+    };
+    let cheapest = |size: usize, skip: Option<usize>| {
+        (0..13)
+            .filter(|&rank| Some(rank) != skip)
+            .filter_map(|rank| need(rank, size).map(|missing| (missing, rank)))
+            .min()
+    };
+
+    if cheapest(4, None).is_some_and(|(missing, _)| missing <= to_come) {
+        return 7;
+    }
+    let full_house = (0..13).any(|trips| {
+        need(trips, 3).is_some_and(|missing| {
+            cheapest(2, Some(trips)).is_some_and(|(pair, _)| missing + pair <= to_come)
+        })
+    });
+    if full_house {
+        return 6;
+    }
+    let flush = SUITS.iter().any(|&suit| {
+        let (held, left) = (cards.suit_mask(suit), live.suit_mask(suit));
+        held.count_ones() as usize + (left.count_ones() as usize).min(to_come) >= 5
+    });
+    if flush {
+        return 5;
+    }
+    let (held_ranks, live_ranks) = (cards.rank_mask(), live.rank_mask());
+    let straight = STRAIGHTS
+        .iter()
+        .any(|&window| drawable(window & !held_ranks, live_ranks));
+    if straight {
+        return 4;
+    }
+    if cheapest(3, None).is_some_and(|(missing, _)| missing <= to_come) {
+        return 3;
+    }
+    match cheapest(2, None) {
+        Some((first, rank)) if first <= to_come => {
+            let second = cheapest(2, Some(rank));
+            if second.is_some_and(|(second, _)| first + second <= to_come) {
+                2
+            } else {
+                1
+            }
+        }
+        _ => 0,
+    }
+}
+
+// The player certain to win outright whatever `to_come` cards of `live` complete `board`,
+// if there is one. Hands only improve as cards come, so it is enough for one player's
+// current category to beat the best every other player could reach.
+pub fn decided_winner(
+    hands: &[CardSet],
+    board: &[Card],
+    live: CardSet,
+    to_come: usize,
+) -> Option<usize> {
+    let board: CardSet = board.iter().copied().collect();
+    let made: Vec<u32> = hands
+        .iter()
+        .map(|&hand| evaluate(hand | board).category())
+        .collect();
+    let leader = (0..hands.len()).max_by_key(|&seat| made[seat])?;
+    let locked = hands
+        .iter()
+        .enumerate()
+        .filter(|&(seat, _)| seat != leader)
+        .all(|(_, &hand)| best_reachable(hand | board, live, to_come) < made[leader]);
+    locked.then_some(leader)
+}
@@ -0,0 +1,82 @@
+use crate::bitboard::CardSet;
+use crate::lookup::evaluate;
+use crate::Card;
+
+// Picks the cards a player throws away at the draw in draw poker. Implement it to
+// simulate another drawing strategy; it is shared by all worker threads.
+pub trait DiscardPolicy: Sync {
+    fn discard(&self, hand: &[Card]) -> CardSet;
+}
+
+// A plain fixed strategy: stand pat on a straight or better, keep pairs, trips and quads,
+// then four to a flush, then four to an open-ended straight, and otherwise keep the high
+// cards. Never draws more than `max_draw` cards, keeping the highest of the rest.
+pub struct StandardDraw {
+    pub max_draw: usize,
+}
+
+// Categories numbered as in filter::CATEGORIES
+const STRAIGHT: u32 = 4;
+
+impl StandardDraw {
+    pub fn new(max_draw: usize) -> StandardDraw {
+        StandardDraw { max_draw }
+    }
+}
+
+impl Default for StandardDraw {
+    fn default() -> Self {
+        StandardDraw::new(3)
+    }
+}
+
+impl DiscardPolicy for StandardDraw {
+    fn discard(&self, hand: &[Card]) -> CardSet {
+        let cards = CardSet::from(hand);
+        if evaluate(cards).category() >= STRAIGHT {
+            return CardSet::new();
+        }
+
+        let count = |card: &Card| hand.iter().filter(|other| other.rank == card.rank).count();
+        let paired: CardSet = hand
+            .iter()
+            .copied()
+            .filter(|card| count(card) > 1)
+            .collect();
+        let flush_draw = hand.iter().find_map(|card| {
+            let suited: CardSet = hand
+                .iter()
+                .copied()
+                .filter(|other| other.suit == card.suit)
+                .collect();
+            (suited.len() == 4).then_some(suited)
+        });
+        // Four consecutive ranks with room on both ends, so neither A-K-Q-J nor A-2-3-4
+        let straight_draw = (5..=13).find_map(|high| {
+            let run: CardSet = hand
+                .iter()
+                .copied()
+                .filter(|card| (high - 3..=high).contains(&(card.rank as u8)))
+                .collect();
+            (run.len() == 4 && run.rank_mask().count_ones() == 4).then_some(run)
+        });
+        let keep = if !paired.is_empty() {
+            paired
+        } else if let Some(flush_draw) = flush_draw {
+            flush_draw
+        } else if let Some(straight_draw) = straight_draw {
+            straight_draw
+        } else {
+            CardSet::new()
+        };
+
+        // Throw away the lowest of the rest, up to the limit
+        let mut rest: Vec<Card> = hand
+            .iter()
+            .copied()
+            .filter(|&card| !keep.contains(card))
+            .collect();
+        rest.sort_by_key(|card| card.rank);
+        rest.into_iter().take(self.max_draw).collect()
+    }
+}
@@ -0,0 +1,576 @@
+use crate::bitboard::CardSet;
+use crate::combinatorics::{choose, unrank_combination};
+use crate::composition::DeckComposition;
+use crate::constraint::HandConstraint;
+use crate::decided::decided_winner;
+use crate::lookup::evaluate_seven;
+use crate::range::Range;
+use crate::rng::{task_rng, Purpose};
+use crate::simulator::{fold_batches, SeatResults};
+use crate::variant::Variant;
+use crate::{Card, Deck, GameResult};
+use itertools::Itertools;
+use rand::Rng;
+use std::fmt;
+
+// What is known about one player's hole cards
+#[derive(Debug, Clone)]
+pub enum Holding {
+    Known(Vec<Card>),
+    // Any hand of the range, each combo as likely as the next
+    Range(Range),
+    // Any hand that meets the constraint
+    Constrained(HandConstraint),
+    Random,
+}
+
+// An equity calculation: players with known hole cards, ranges of hands or hands only known
+// to meet a constraint, optionally joined by players holding random hands, on a partially
+// known board
+pub struct Scenario {
+    pub seats: Vec<Holding>,
+    pub board: Vec<Card>,
+    // Every hand each ranged or constrained seat may hold around the known cards, in seat
+    // order
+    combos: Vec<Vec<[Card; 2]>>,
+}
+
+// Exact enumeration refuses to play more deals than this; sampling is the way there
+const MAX_EXACT_DEALS: usize = 500_000_000;
+
+impl Scenario {
+    pub fn new(seats: Vec<Holding>, board: Vec<Card>) -> Result<Scenario, String> {
+        let has_hand = seats
+            .iter()
+            .any(|holding| matches!(holding, Holding::Known(_) | Holding::Range(_)));
+        if !has_hand || seats.len() < 2 {
+            return Err(
+                "an equity calculation needs at least one known hand or range and two players"
+                    .to_string(),
+            );
+        }
+        let hands: Vec<&Vec<Card>> = seats
+            .iter()
+            .filter_map(|holding| match holding {
+                Holding::Known(hand) => Some(hand),
+                _ => None,
+            })
+            .collect();
+        if let Some(hand) = hands.iter().find(|hand| hand.len() != 2) {
+            return Err(format!("hands have two hole cards, got {}", hand.len()));
+        }
+        // A flop, turn or river, or no board yet
+        if !matches!(board.len(), 0 | 3 | 4 | 5) {
+            return Err(format!(
+                "a board has three, four or five cards, got {}",
+                board.len()
+            ));
+        }
+        if 2 * seats.len() + 5 > 52 {
+            return Err("too many players for one deck".to_string());
+        }
+        let mut known = CardSet::new();
+        for &card in hands.into_iter().flatten().chain(&board) {
+            if known.contains(card) {
+                return Err(format!("card {} appears twice", card));
+            }
+            known.insert(card);
+        }
+
+        let mut combos = Vec::new();
+        for holding in &seats {
+            let hands: Vec<[Card; 2]> = match holding {
+                Holding::Range(range) => range
+                    .combos()
+                    .iter()
+                    .filter(|hand| (CardSet::from(&hand[..]) & known).is_empty())
+                    .copied()
+                    .collect(),
+                Holding::Constrained(constraint) => DeckComposition::new(known)
+                    .hands()
+                    .into_iter()
+                    .filter(|hand| constraint.matches(hand))
+                    .collect(),
+                Holding::Known(_) | Holding::Random => continue,
+            };
+            if hands.is_empty() {
+                return Err(match holding {
+                    Holding::Range(range) => {
+                        format!("no hand in {} is left around the known cards", range)
+                    }
+                    _ => format!("no hand matches {} around the known cards", holding),
+                });
+            }
+            combos.push(hands);
+        }
+        if !has_assignment(&combos, known) {
+            return Err("no deal meets every range and constraint at once".to_string());
+        }
+        Ok(Scenario {
+            seats,
+            board,
+            combos,
+        })
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.seats.len()
+    }
+
+    // Whether every player's hole cards are known, so only the board is left to deal
+    pub fn all_known(&self) -> bool {
+        self.seats
+            .iter()
+            .all(|holding| matches!(holding, Holding::Known(_)))
+    }
+
+    fn random_players(&self) -> usize {
+        self.seats
+            .iter()
+            .filter(|holding| matches!(holding, Holding::Random))
+            .count()
+    }
+
+    fn known_cards(&self) -> CardSet {
+        self.seats
+            .iter()
+            .filter_map(|holding| match holding {
+                Holding::Known(hand) => Some(hand),
+                _ => None,
+            })
+            .flatten()
+            .chain(&self.board)
+            .copied()
+            .collect()
+    }
+
+    // Hands for every ranged or constrained seat drawn uniformly from the deals that fit
+    // them all: each hand is drawn from the seat's own combos and the draw is repeated
+    // whenever two of them share a card, which keeps the weight of every combo equal once
+    // card removal is accounted for
+    fn deal_ranges<R: Rng>(&self, rng: &mut R) -> (Vec<[Card; 2]>, CardSet) {
+        'draw: loop {
+            let mut used = CardSet::new();
+            let mut hands = Vec::with_capacity(self.combos.len());
+            for combos in &self.combos {
+                let hand = combos[rng.gen_range(0..combos.len())];
+                if hand.iter().any(|&card| used.contains(card)) {
+                    continue 'draw;
+                }
+                used = used | CardSet::from(&hand[..]);
+                hands.push(hand);
+            }
+            return (hands, used);
+        }
+    }
+
+    // Play out one deal: ranged and constrained seats hold the `ranged` hands in turn and
+    // random players the `random` ones, then `deal` supplies the hands of any remaining
+    // random players and the rest of the board
+    fn showdown(
+        &self,
+        ranged: &[[Card; 2]],
+        random: &[[Card; 2]],
+        mut deal: impl FnMut() -> Card,
+    ) -> GameResult {
+        let hole_cards = self.hole_cards(ranged, random, &mut deal);
+        let mut board = self.board.clone();
+        while board.len() < 5 {
+            board.push(deal());
+        }
+        play(hole_cards, board)
+    }
+
+    // Every seat's hole cards, dealt as in `showdown`
+    fn hole_cards(
+        &self,
+        ranged: &[[Card; 2]],
+        random: &[[Card; 2]],
+        mut deal: impl FnMut() -> Card,
+    ) -> Vec<Vec<Card>> {
+        let mut ranged = ranged.iter();
+        let mut random = random.iter();
+        self.seats
+            .iter()
+            .map(|holding| match holding {
+                Holding::Known(hand) => hand.clone(),
+                Holding::Range(_) | Holding::Constrained(_) => ranged.next().unwrap().to_vec(),
+                Holding::Random => match random.next() {
+                    Some(hand) => hand.to_vec(),
+                    None => vec![deal(), deal()],
+                },
+            })
+            .collect()
+    }
+}
+
+// The showdown of Hold'em hands on a complete board
+fn play(hole_cards: Vec<Vec<Card>>, board: Vec<Card>) -> GameResult {
+    let hand_ranks = hole_cards
+        .iter()
+        .map(|hole_cards| {
+            let mut cards = [board[0]; 7];
+            cards[..2].copy_from_slice(hole_cards);
+            cards[2..].copy_from_slice(&board);
+            evaluate_seven(&cards).to_hand_rank()
+        })
+        .collect();
+    let folded = vec![false; hole_cards.len()];
+    GameResult::from_showdown(Variant::Holdem, hole_cards, board, hand_ranks, folded)
+}
+
+impl fmt::Display for Holding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Holding::Known(hand) => write!(f, "{}", hand.iter().map(Card::to_string).join("")),
+            Holding::Range(range) => write!(f, "{}", range),
+            Holding::Constrained(constraint) => write!(f, "[{}]", constraint),
+            Holding::Random => write!(f, "Random"),
+        }
+    }
+}
+
+// Whether the seats can all be dealt hands from their combos without sharing a card
+fn has_assignment(combos: &[Vec<[Card; 2]>], used: CardSet) -> bool {
+    match combos.split_first() {
+        None => true,
+        Some((first, rest)) => first.iter().any(|hand| {
+            let hand = CardSet::from(&hand[..]);
+            (hand & used).is_empty() && has_assignment(rest, used | hand)
+        }),
+    }
+}
+
+// Hands dealt to the seats that draw from combos, in seat order
+type Assignment = Vec<[Card; 2]>;
+
+// The number of ways to deal the seats hands from their combos without sharing a card, or
+// None once there are more than `limit`; counted without building them
+fn count_assignments(combos: &[Vec<[Card; 2]>], used: CardSet, limit: usize) -> Option<usize> {
+    let (first, rest) = match combos.split_first() {
+        None => return Some(1),
+        Some(split) => split,
+    };
+    let mut count = 0;
+    for hand in first {
+        let cards = CardSet::from(&hand[..]);
+        if !(cards & used).is_empty() {
+            continue;
+        }
+        count += count_assignments(rest, used | cards, limit - count)?;
+        if count > limit {
+            return None;
+        }
+    }
+    Some(count)
+}
+
+// Every way to deal the seats hands from their combos without sharing a card
+fn assignments(combos: &[Vec<[Card; 2]>], used: CardSet) -> Vec<Assignment> {
+    let (first, rest) = match combos.split_first() {
+        None => return vec![Vec::new()],
+        Some(split) => split,
+    };
+    let mut all = Vec::new();
+    for hand in first {
+        let cards = CardSet::from(&hand[..]);
+        if !(cards & used).is_empty() {
+            continue;
+        }
+        for mut tail in assignments(rest, used | cards) {
+            tail.insert(0, *hand);
+            all.push(tail);
+        }
+    }
+    all
+}
+
+fn merge_seats(mut totals: Vec<SeatResults>, other: Vec<SeatResults>) -> Vec<SeatResults> {
+    for (total, seat_results) in totals.iter_mut().zip(&other) {
+        total.merge(seat_results);
+    }
+    totals
+}
+
+// Win, tie and equity counts per player over `trials` random deals of the unknown cards,
+// reproducible from `seed`
+pub fn sample_equity(scenario: &Scenario, trials: usize, seed: u64) -> Vec<SeatResults> {
+    let num_players = scenario.num_players();
+    fold_batches(
+        0..trials,
+        || vec![SeatResults::default(); num_players],
+        |mut local, games| {
+            let mut rng = task_rng(seed, Purpose::Deal, games.start as u64);
+            let known = scenario.known_cards();
+            for _ in games {
+                let (ranged, used) = scenario.deal_ranges(&mut rng);
+                let mut deck = Deck::from_cards((!(known | used)).to_vec());
+                deck.shuffle(&mut rng);
+                let result = scenario.showdown(&ranged, &[], || deck.deal().unwrap());
+                for (seat, seat_results) in local.iter_mut().enumerate() {
+                    seat_results.record(&result, seat);
+                }
+            }
+            local
+        },
+        merge_seats,
+    )
+}
+
+// Every deal of the unknown hands for exact enumeration, random players included, along
+// with the number of cards left to run the board out from
+fn exact_assignments(scenario: &Scenario) -> Result<(Vec<Assignment>, usize), String> {
+    let too_many = || {
+        format!(
+            "more than {} deals to enumerate; sample them with --trials instead",
+            MAX_EXACT_DEALS
+        )
+    };
+    // Players with random hands are ranged seats holding any hand
+    let known = scenario.known_cards();
+    let mut combos = scenario.combos.clone();
+    let hands = DeckComposition::new(known).hands();
+    combos.extend((0..scenario.random_players()).map(|_| hands.clone()));
+    let unknown = 52 - known.len() - 2 * combos.len();
+    let to_come = 5 - scenario.board.len();
+    let runouts = choose(unknown as u64, to_come as u64) as usize;
+    // Count before building: the assignments alone can outgrow memory long before the
+    // runouts are multiplied in
+    count_assignments(&combos, known, MAX_EXACT_DEALS / runouts).ok_or_else(too_many)?;
+    let assignments = assignments(&combos, known);
+    Ok((assignments, unknown))
+}
+
+// Exact win, tie and equity counts per player over every possible deal of the unknown
+// hands and runout of the board, along with the number of deals
+pub fn exact_equity(scenario: &Scenario) -> Result<(Vec<SeatResults>, usize), String> {
+    let (assignments, unknown) = exact_assignments(scenario)?;
+    let known = scenario.known_cards();
+    let to_come = 5 - scenario.board.len();
+    let runouts = choose(unknown as u64, to_come as u64) as usize;
+    let deals = assignments.len() * runouts;
+
+    let num_players = scenario.num_players();
+    let totals = fold_batches(
+        0..deals,
+        || vec![SeatResults::default(); num_players],
+        |mut local, deals| {
+            // Consecutive deals mostly share their unknown hands
+            let mut current: Option<(usize, Vec<Card>)> = None;
+            for deal in deals {
+                let index = deal / runouts;
+                let assignment = &assignments[index];
+                if current.as_ref().map(|(i, _)| *i) != Some(index) {
+                    let used: CardSet = assignment.iter().flatten().copied().collect();
+                    current = Some((index, (!(known | used)).to_vec()));
+                }
+                let remaining = &current.as_ref().unwrap().1;
+                let runout = (deal % runouts) as u64;
+                let indices = unrank_combination(runout, remaining.len() as u64, to_come as u64);
+                let mut cards = indices.into_iter().map(|index| remaining[index]);
+                let (ranged, random) = assignment.split_at(scenario.combos.len());
+                let result = scenario.showdown(ranged, random, || cards.next().unwrap());
+                for (seat, seat_results) in local.iter_mut().enumerate() {
+                    seat_results.record(&result, seat);
+                }
+            }
+            local
+        },
+        merge_seats,
+    );
+    Ok((totals, deals))
+}
+
+// The same as exact_equity, but the board is dealt street by street and a branch whose
+// winner is already certain on the flop or turn, see decided.rs, is credited to them
+// whole instead of being run out. Wins, ties and equity match exact_equity exactly; hand
+// categories only count the deals played out to the river.
+pub fn exact_equity_by_street(scenario: &Scenario) -> Result<(Vec<SeatResults>, usize), String> {
+    let (assignments, unknown) = exact_assignments(scenario)?;
+    let known = scenario.known_cards();
+    let to_come = 5 - scenario.board.len();
+    let deals = assignments.len() * choose(unknown as u64, to_come as u64) as usize;
+
+    // Each task deals the runouts of one assignment and one flop, so even a single
+    // assignment spreads over the threads. A runout's flop is its lowest cards in deck
+    // order, which leaves each runout in exactly one task.
+    let to_flop = 3usize.saturating_sub(scenario.board.len());
+    let flops = choose(unknown as u64, to_flop as u64) as usize;
+    let num_players = scenario.num_players();
+    let totals = fold_batches(
+        0..assignments.len() * flops,
+        || vec![SeatResults::default(); num_players],
+        |mut local, tasks| {
+            for task in tasks {
+                let assignment = &assignments[task / flops];
+                let (ranged, random) = assignment.split_at(scenario.combos.len());
+                let hole_cards = scenario.hole_cards(ranged, random, || unreachable!());
+                let used: CardSet = assignment.iter().flatten().copied().collect();
+                let remaining = (!(known | used)).to_vec();
+                let mut live = !(known | used);
+                let mut board = scenario.board.clone();
+                let flop =
+                    unrank_combination((task % flops) as u64, unknown as u64, to_flop as u64);
+                for &index in &flop {
+                    board.push(remaining[index]);
+                    live.remove(remaining[index]);
+                }
+                let start = flop.last().map_or(0, |&index| index + 1);
+                let end = unknown + 1 - (5 - board.len());
+                let mut street = Street {
+                    hands: hole_cards
+                        .iter()
+                        .map(|hand| CardSet::from(&hand[..]))
+                        .collect(),
+                    hole_cards,
+                    board,
+                    remaining,
+                    results: &mut local,
+                };
+                street.deal(live, start..end);
+            }
+            local
+        },
+        merge_seats,
+    );
+    Ok((totals, deals))
+}
+
+// One assignment's board being dealt street by street
+struct Street<'a> {
+    hole_cards: Vec<Vec<Card>>,
+    hands: Vec<CardSet>,
+    board: Vec<Card>,
+    // Cards the board is dealt from, in deck order
+    remaining: Vec<Card>,
+    results: &'a mut [SeatResults],
+}
+
+impl Street<'_> {
+    // Deal the rest of the board with its next card from `remaining[next]` and the cards
+    // after it in increasing order, so every runout is dealt once across the calls. `live`
+    // holds the remaining cards not yet on the board.
+    fn deal(&mut self, live: CardSet, next: std::ops::Range<usize>) {
+        let to_come = 5 - self.board.len();
+        if to_come == 0 {
+            let result = play(self.hole_cards.clone(), self.board.clone());
+            for (seat, seat_results) in self.results.iter_mut().enumerate() {
+                seat_results.record(&result, seat);
+            }
+            return;
+        }
+        if self.board.len() >= 3 {
+            if let Some(winner) = decided_winner(&self.hands, &self.board, live, to_come) {
+                // Runouts whose next card is in `next`
+                let runouts = |from: usize| {
+                    choose((self.remaining.len() - from) as u64, to_come as u64) as usize
+                };
+                self.results[winner].record_wins(runouts(next.start) - runouts(next.end));
+                return;
+            }
+        }
+        let end = self.remaining.len() + 2 - to_come;
+        for i in next {
+            let card = self.remaining[i];
+            self.board.push(card);
+            let mut live = live;
+            live.remove(card);
+            self.deal(live, i + 1..end);
+            self.board.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_cards;
+
+    fn scenario(hands: &[&str], random: usize, board: &str) -> Scenario {
+        let mut seats: Vec<Holding> = hands
+            .iter()
+            .map(|hand| Holding::Known(parse_cards(hand).unwrap()))
+            .collect();
+        seats.extend((0..random).map(|_| Holding::Random));
+        Scenario::new(seats, parse_cards(board).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn too_many_deals_is_an_error() {
+        // Billions of hole-card assignments before a runout is counted; refused before
+        // any of them is built
+        assert!(exact_equity(&scenario(&["AsKs"], 3, "")).is_err());
+        assert!(exact_equity(&scenario(&["AsKs"], 2, "")).is_err());
+        assert!(exact_equity_by_street(&scenario(&["AsKs"], 3, "")).is_err());
+    }
+
+    #[test]
+    fn partial_boards_are_refused() {
+        let seats = || {
+            vec![
+                Holding::Known(parse_cards("AsKs").unwrap()),
+                Holding::Random,
+            ]
+        };
+        for board in ["2c", "2c 7d", "2c 7d 9h Jc Qd Kh"] {
+            let board = parse_cards(board).unwrap();
+            let count = board.len();
+            assert_eq!(
+                Scenario::new(seats(), board).err(),
+                Some(format!(
+                    "a board has three, four or five cards, got {}",
+                    count
+                ))
+            );
+        }
+        for board in ["", "2c 7d 9h", "2c 7d 9h Jc", "2c 7d 9h Jc Qd"] {
+            assert!(Scenario::new(seats(), parse_cards(board).unwrap()).is_ok());
+        }
+    }
+
+    #[test]
+    fn random_players_on_a_full_board() {
+        let (_, deals) = exact_equity(&scenario(&["AsKs"], 1, "2c 7d 9h Jc Qd")).unwrap();
+        assert_eq!(deals, 990);
+    }
+
+    // Skipping decided runouts must not change any win, tie or equity count; returns how
+    // many runouts were skipped
+    fn assert_streets_agree(scenario: &Scenario) -> usize {
+        let (full, deals) = exact_equity(scenario).unwrap();
+        let (by_street, street_deals) = exact_equity_by_street(scenario).unwrap();
+        assert_eq!(deals, street_deals);
+        for (full, by_street) in full.iter().zip(&by_street) {
+            assert_eq!(full.wins, by_street.wins);
+            assert_eq!(full.ties, by_street.ties);
+            assert!((full.tie_equity - by_street.tie_equity).abs() < 1e-6);
+            assert!((full.equity_squares - by_street.equity_squares).abs() < 1e-6);
+        }
+        deals - by_street[0].categories.iter().sum::<usize>()
+    }
+
+    #[test]
+    fn dominated_hand_by_street() {
+        assert_streets_agree(&scenario(&["AsKd", "AcQh"], 0, "Kc 8h 3s"));
+        assert_streets_agree(&scenario(&["AsKd", "AcQh"], 0, "Kc 8h 3s Qd"));
+    }
+
+    #[test]
+    fn flush_over_straight_by_street() {
+        // The flush is already made and nothing left can beat it
+        assert!(assert_streets_agree(&scenario(&["AhKh", "8c7d"], 0, "9h Th Jh 2s")) > 0);
+        assert_streets_agree(&scenario(&["AhKh", "8c7d"], 0, "9h Th 2h"));
+    }
+
+    #[test]
+    fn board_plays_by_street() {
+        assert_streets_agree(&scenario(&["2c3d", "4h5h"], 0, "Ts Js Qs Ks"));
+        assert_streets_agree(&scenario(&["2c3d", "4h6c"], 0, "Ts Js Qs"));
+    }
+
+    #[test]
+    fn multiway_by_street() {
+        assert_streets_agree(&scenario(&["AsAd", "KcKh", "7s8s", "2d2h"], 0, "Ks 6s 5d"));
+        assert_streets_agree(&scenario(&["AsAd", "KcKh", "7s8s"], 1, "Ks 6s 5d 2c"));
+    }
+}
@@ -0,0 +1,605 @@
+use crate::{GameResult, HandRank, Rank};
+use itertools::Itertools;
+use std::fmt;
+
+// Hand filter expressions such as "category >= Flush && players_in >= 3 && board.paired",
+// parsed once and then evaluated against every finished game. Hero fields refer to
+// seat 1 and villain fields to the best of the other seats.
+
+// Shorthand names for common filters
+const PRESETS: &[(&str, &str)] = &[
+    ("quads-beaten", "beaten >= FourOfAKind"),
+    ("straight-flush", "category >= StraightFlush"),
+    (
+        "cooler",
+        "beaten >= FullHouse || (winners >= 2 && category >= FullHouse)",
+    ),
+];
+
+// Category names in ascending strength, matching the HandRank variants
+pub const CATEGORIES: [&str; 10] = [
+    "HighCard",
+    "OnePair",
+    "TwoPair",
+    "ThreeOfAKind",
+    "Straight",
+    "Flush",
+    "FullHouse",
+    "FourOfAKind",
+    "StraightFlush",
+    "RoyalFlush",
+];
+
+pub fn category_value(hand_rank: &HandRank) -> i64 {
+    match hand_rank {
+        HandRank::HighCard(_) => 0,
+        HandRank::OnePair(_, _) => 1,
+        HandRank::TwoPair(_, _, _) => 2,
+        HandRank::ThreeOfAKind(_, _) => 3,
+        HandRank::Straight(_) => 4,
+        HandRank::Flush(_) => 5,
+        HandRank::FullHouse(_, _) => 6,
+        HandRank::FourOfAKind(_, _) => 7,
+        HandRank::StraightFlush(_) => 8,
+        HandRank::RoyalFlush => 9,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Number,
+    Category,
+}
+
+// Field names as written in filter expressions
+const FIELDS: [(&str, Field, Kind); 16] = [
+    ("category", Field::Category, Kind::Category),
+    ("beaten", Field::Beaten, Kind::Category),
+    ("players_in", Field::PlayersIn, Kind::Number),
+    ("winners", Field::Winners, Kind::Number),
+    ("board.paired", Field::BoardPaired, Kind::Bool),
+    ("board.trips", Field::BoardTrips, Kind::Bool),
+    (
+        "board.flush_possible",
+        Field::BoardFlushPossible,
+        Kind::Bool,
+    ),
+    (
+        "board.straight_possible",
+        Field::BoardStraightPossible,
+        Kind::Bool,
+    ),
+    ("board.four_flush", Field::BoardFourFlush, Kind::Bool),
+    ("hero.category", Field::HeroCategory, Kind::Category),
+    ("hero.win", Field::HeroWin, Kind::Bool),
+    ("hero.flop", Field::HeroFlop, Kind::Category),
+    ("hero.top_pair", Field::HeroTopPair, Kind::Bool),
+    ("hero.pocket_pair", Field::HeroPocketPair, Kind::Bool),
+    ("hero.suited", Field::HeroSuited, Kind::Bool),
+    ("villain.category", Field::VillainCategory, Kind::Category),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    // Category of the winning hand
+    Category,
+    // Best category among the hands that lost (HighCard when nobody lost)
+    Beaten,
+    PlayersIn,
+    Winners,
+    BoardPaired,
+    BoardTrips,
+    BoardFlushPossible,
+    BoardStraightPossible,
+    BoardFourFlush,
+    HeroCategory,
+    // Hero won at least a share of the pot, including either half of a hi-lo pot
+    HeroWin,
+    // Hero's category using only the flop
+    HeroFlop,
+    // Hero paired the highest flop card with a hole card
+    HeroTopPair,
+    HeroPocketPair,
+    HeroSuited,
+    VillainCategory,
+}
+
+impl Field {
+    fn lookup(name: &str) -> Option<(Field, Kind)> {
+        FIELDS
+            .iter()
+            .find(|(field_name, _, _)| *field_name == name)
+            .map(|&(_, field, kind)| (field, kind))
+    }
+
+    fn name(&self) -> &'static str {
+        FIELDS
+            .iter()
+            .find(|(_, field, _)| field == self)
+            .map(|(name, _, _)| *name)
+            .unwrap()
+    }
+
+    fn value(&self, result: &GameResult) -> i64 {
+        let best = result.best_hand();
+        match self {
+            Field::Category => category_value(best),
+            Field::Beaten => result
+                .hand_ranks
+                .iter()
+                .zip(&result.folded)
+                .filter(|&(rank, &folded)| {
+                    !folded && result.variant.strength(rank) < result.variant.strength(best)
+                })
+                .map(|(rank, _)| rank)
+                .map(category_value)
+                .max()
+                .unwrap_or(0),
+            Field::PlayersIn => result.players_in() as i64,
+            Field::Winners => result.winners.len() as i64,
+            Field::BoardPaired => (max_rank_count(result) >= 2) as i64,
+            Field::BoardTrips => (max_rank_count(result) >= 3) as i64,
+            Field::BoardFlushPossible => (max_suit_count(result) >= 3) as i64,
+            Field::BoardFourFlush => (max_suit_count(result) >= 4) as i64,
+            Field::BoardStraightPossible => {
+                // Three board ranks inside any five-rank window, counting the ace low too
+                let mut present = [false; 15];
+                for card in &result.board {
+                    present[card.rank as usize] = true;
+                    if card.rank == Rank::Ace {
+                        present[1] = true;
+                    }
+                }
+                let possible =
+                    (1..=10).any(|low| present[low..low + 5].iter().filter(|&&p| p).count() >= 3);
+                possible as i64
+            }
+            Field::HeroCategory => category_value(&result.hand_ranks[0]),
+            Field::HeroWin => (result.equity(0) > 0.0) as i64,
+            Field::HeroFlop => category_value(&hero_flop_rank(result)),
+            Field::HeroTopPair => {
+                // Games without a board have no top pair
+                let top = result.board.iter().take(3).map(|card| card.rank).max();
+                let pairs_top = result.hole_cards[0]
+                    .iter()
+                    .any(|card| Some(card.rank) == top);
+                (pairs_top && matches!(hero_flop_rank(result), HandRank::OnePair(_, _))) as i64
+            }
+            // With more than two hole cards, any two of them
+            Field::HeroPocketPair => {
+                let hole_cards = &result.hole_cards[0];
+                let ranks = hole_cards.iter().map(|card| card.rank);
+                (ranks.unique().count() < hole_cards.len()) as i64
+            }
+            Field::HeroSuited => {
+                let hole_cards = &result.hole_cards[0];
+                let suits = hole_cards.iter().map(|card| card.suit);
+                (suits.unique().count() < hole_cards.len()) as i64
+            }
+            Field::VillainCategory => result.hand_ranks[1..]
+                .iter()
+                .map(category_value)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
+// The hero's hand on the flop, or on fifth street in Stud
+fn hero_flop_rank(result: &GameResult) -> HandRank {
+    let variant = result.variant;
+    let (hole_cards, board) = variant.street_cards(&result.hole_cards[0], &result.board, 0);
+    variant.evaluate(hole_cards, board).to_hand_rank()
+}
+
+fn max_suit_count(result: &GameResult) -> u8 {
+    let mut suit_counts = [0u8; 4];
+    for card in &result.board {
+        suit_counts[card.suit as usize] += 1;
+    }
+    *suit_counts.iter().max().unwrap()
+}
+
+fn max_rank_count(result: &GameResult) -> u8 {
+    let mut rank_counts = [0u8; 15];
+    for card in &result.board {
+        rank_counts[card.rank as usize] += 1;
+    }
+    *rank_counts.iter().max().unwrap()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Field(Field),
+    Literal(i64, Kind),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn value(&self, result: &GameResult) -> i64 {
+        match self {
+            Expr::Field(field) => field.value(result),
+            Expr::Literal(value, _) => *value,
+            _ => self.test(result) as i64,
+        }
+    }
+
+    fn test(&self, result: &GameResult) -> bool {
+        match self {
+            Expr::Not(inner) => !inner.test(result),
+            Expr::And(lhs, rhs) => lhs.test(result) && rhs.test(result),
+            Expr::Or(lhs, rhs) => lhs.test(result) || rhs.test(result),
+            Expr::Compare(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.value(result), rhs.value(result));
+                match op {
+                    CompareOp::Eq => lhs == rhs,
+                    CompareOp::Ne => lhs != rhs,
+                    CompareOp::Lt => lhs < rhs,
+                    CompareOp::Le => lhs <= rhs,
+                    CompareOp::Gt => lhs > rhs,
+                    CompareOp::Ge => lhs >= rhs,
+                }
+            }
+            _ => self.value(result) != 0,
+        }
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+// Canonical form: fully parenthesized, single-spaced, with presets expanded
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Field(field) => write!(f, "{}", field.name()),
+            Expr::Literal(value, Kind::Category) => write!(f, "{}", CATEGORIES[*value as usize]),
+            Expr::Literal(value, Kind::Bool) => write!(f, "{}", *value != 0),
+            Expr::Literal(value, Kind::Number) => write!(f, "{}", value),
+            Expr::Not(inner) => match **inner {
+                Expr::Field(_) | Expr::Literal(..) => write!(f, "!{}", inner),
+                _ => write!(f, "!({})", inner),
+            },
+            Expr::And(lhs, rhs) => write!(f, "({} && {})", lhs, rhs),
+            Expr::Or(lhs, rhs) => write!(f, "({} || {})", lhs, rhs),
+            Expr::Compare(op, lhs, rhs) => write!(f, "{} {} {}", lhs, op, rhs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(i64),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    const OPS: [&str; 9] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!"];
+
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '(' || c == ')' {
+            tokens.push(if c == '(' {
+                Token::LParen
+            } else {
+                Token::RParen
+            });
+            rest = &rest[1..];
+        } else if let Some(op) = OPS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            tokens.push(match word.parse() {
+                Ok(number) => Token::Number(number),
+                Err(_) => Token::Ident(word.to_string()),
+            });
+            rest = &rest[end..];
+        } else {
+            return Err(format!("unexpected character '{}'", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat_op(&mut self, op: &'static str) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_bool(expr: Expr, kind: Kind) -> Result<Expr, String> {
+        if kind == Kind::Bool {
+            Ok(expr)
+        } else {
+            Err("expected a condition, found a value".to_string())
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<(Expr, Kind), String> {
+        let (mut lhs, kind) = self.parse_and()?;
+        if self.peek() != Some(&Token::Op("||")) {
+            return Ok((lhs, kind));
+        }
+        lhs = Self::expect_bool(lhs, kind)?;
+        while self.eat_op("||") {
+            let (rhs, kind) = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(Self::expect_bool(rhs, kind)?));
+        }
+        Ok((lhs, Kind::Bool))
+    }
+
+    fn parse_and(&mut self) -> Result<(Expr, Kind), String> {
+        let (mut lhs, kind) = self.parse_unary()?;
+        if self.peek() != Some(&Token::Op("&&")) {
+            return Ok((lhs, kind));
+        }
+        lhs = Self::expect_bool(lhs, kind)?;
+        while self.eat_op("&&") {
+            let (rhs, kind) = self.parse_unary()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(Self::expect_bool(rhs, kind)?));
+        }
+        Ok((lhs, Kind::Bool))
+    }
+
+    fn parse_unary(&mut self) -> Result<(Expr, Kind), String> {
+        if self.eat_op("!") {
+            let (inner, kind) = self.parse_unary()?;
+            return Ok((
+                Expr::Not(Box::new(Self::expect_bool(inner, kind)?)),
+                Kind::Bool,
+            ));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<(Expr, Kind), String> {
+        let (lhs, lhs_kind) = self.parse_atom()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => CompareOp::Eq,
+            Some(Token::Op("!=")) => CompareOp::Ne,
+            Some(Token::Op("<")) => CompareOp::Lt,
+            Some(Token::Op("<=")) => CompareOp::Le,
+            Some(Token::Op(">")) => CompareOp::Gt,
+            Some(Token::Op(">=")) => CompareOp::Ge,
+            _ => return Ok((lhs, lhs_kind)),
+        };
+        self.pos += 1;
+        let (rhs, rhs_kind) = self.parse_atom()?;
+        if lhs_kind != rhs_kind {
+            return Err(format!("cannot compare {:?} with {:?}", lhs_kind, rhs_kind));
+        }
+        Ok((Expr::Compare(op, Box::new(lhs), Box::new(rhs)), Kind::Bool))
+    }
+
+    fn parse_atom(&mut self) -> Result<(Expr, Kind), String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "unexpected end of filter".to_string())?;
+        self.pos += 1;
+        match token {
+            Token::LParen => {
+                let inner = self.parse_or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err("expected ')'".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Token::Number(number) => Ok((Expr::Literal(number, Kind::Number), Kind::Number)),
+            Token::Ident(name) => {
+                if let Some((field, kind)) = Field::lookup(&name) {
+                    Ok((Expr::Field(field), kind))
+                } else if let Some(index) = CATEGORIES.iter().position(|&c| c == name) {
+                    Ok((Expr::Literal(index as i64, Kind::Category), Kind::Category))
+                } else if name == "true" || name == "false" {
+                    Ok((
+                        Expr::Literal((name == "true") as i64, Kind::Bool),
+                        Kind::Bool,
+                    ))
+                } else {
+                    Err(format!("unknown name: {}", name))
+                }
+            }
+            Token::Op(op) => Err(format!("unexpected '{}'", op)),
+            Token::RParen => Err("unexpected ')'".to_string()),
+        }
+    }
+}
+
+// A parsed filter expression
+#[derive(Debug, Clone)]
+pub struct HandFilter {
+    source: String,
+    expr: Expr,
+}
+
+impl HandFilter {
+    pub fn parse(text: &str) -> Result<HandFilter, String> {
+        let text = text.trim();
+        let source = PRESETS
+            .iter()
+            .find(|(name, _)| *name == text)
+            .map_or(text, |(_, expansion)| expansion);
+
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+        let (expr, kind) = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {:?} in filter", token));
+        }
+        let expr = Parser::expect_bool(expr, kind)?;
+
+        Ok(HandFilter {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    // Normalized text of the filter; equivalent spellings share the same form
+    pub fn canonical(&self) -> String {
+        self.expr.to_string()
+    }
+
+    pub fn matches(&self, result: &GameResult) -> bool {
+        self.expr.test(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate_hand;
+    use crate::notation::parse_cards;
+    use crate::variant::Variant;
+
+    fn canonical(text: &str) -> String {
+        HandFilter::parse(text).unwrap().canonical()
+    }
+
+    fn error(text: &str) -> String {
+        HandFilter::parse(text).unwrap_err()
+    }
+
+    #[test]
+    fn precedence() {
+        // ! binds tightest, then comparisons, then &&, then ||
+        assert_eq!(
+            canonical("hero.win || players_in >= 3 && board.paired"),
+            "(hero.win || (players_in >= 3 && board.paired))"
+        );
+        assert_eq!(
+            canonical("(hero.win || players_in >= 3) && board.paired"),
+            "((hero.win || players_in >= 3) && board.paired)"
+        );
+        assert_eq!(
+            canonical("!hero.win && !(board.paired || board.trips)"),
+            "(!hero.win && !((board.paired || board.trips)))"
+        );
+        assert_eq!(
+            canonical("hero.win || board.trips || hero.suited"),
+            "((hero.win || board.trips) || hero.suited)"
+        );
+    }
+
+    #[test]
+    fn spellings_share_a_canonical_form() {
+        assert_eq!(
+            canonical("category>=Flush&&players_in>=3"),
+            canonical("  category >= Flush  &&  players_in >= 3 ")
+        );
+        assert_eq!(canonical("quads-beaten"), "beaten >= FourOfAKind");
+        assert_eq!(
+            HandFilter::parse("cooler").unwrap().source(),
+            "beaten >= FullHouse || (winners >= 2 && category >= FullHouse)"
+        );
+    }
+
+    #[test]
+    fn unknown_names() {
+        assert_eq!(error("hero.wins"), "unknown name: hero.wins");
+        // Presets only stand for a whole filter
+        assert_eq!(error("cooler && hero.win"), "unknown name: cooler");
+        assert_eq!(error("category >= Flushes"), "unknown name: Flushes");
+    }
+
+    #[test]
+    fn malformed_filters() {
+        assert_eq!(error(""), "unexpected end of filter");
+        assert_eq!(error("category >="), "unexpected end of filter");
+        assert_eq!(error("(hero.win"), "expected ')'");
+        assert_eq!(error("hero.win)"), "unexpected RParen in filter");
+        assert_eq!(error("hero.win && && board.paired"), "unexpected '&&'");
+        assert_eq!(
+            error("category >= 3"),
+            "cannot compare Category with Number"
+        );
+        assert_eq!(error("players_in"), "expected a condition, found a value");
+        assert_eq!(
+            error("players_in && hero.win"),
+            "expected a condition, found a value"
+        );
+        assert_eq!(error("hero.win $ board.paired"), "unexpected character '$'");
+    }
+
+    #[test]
+    fn matches_a_showdown() {
+        let hole_cards = vec![parse_cards("As Ad").unwrap(), parse_cards("Ks Kd").unwrap()];
+        let board = parse_cards("Ac Kc 2d 7h 7s").unwrap();
+        let hand_ranks = hole_cards
+            .iter()
+            .map(|hand| evaluate_hand(&[&hand[..], &board[..]].concat()))
+            .collect();
+        let result = GameResult::from_showdown(
+            Variant::Holdem,
+            hole_cards,
+            board,
+            hand_ranks,
+            vec![false; 2],
+        );
+        let matches = |text: &str| HandFilter::parse(text).unwrap().matches(&result);
+        assert!(matches(
+            "hero.win && category == FullHouse && beaten == FullHouse"
+        ));
+        assert!(matches("cooler"));
+        assert!(matches("board.paired && hero.pocket_pair"));
+        assert!(!matches("hero.suited || board.trips || winners >= 2"));
+        assert!(matches(
+            "villain.category == FullHouse && hero.flop == ThreeOfAKind"
+        ));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{Card, Deck, Rank};
+use itertools::Itertools;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+// Optional preflop folding: every seat plays only the strongest `vpip` share of starting
+// hands, ranked by the Chen formula, and folds the rest. The share is either the same for
+// every seat or set per position by a table preset. The last seat is the big blind and
+// never folds, so every game still reaches a showdown or a walk.
+#[derive(Debug, Clone)]
+pub struct FoldModel {
+    // One cutoff for every seat, or one per seat
+    cutoffs: Vec<Cutoff>,
+    table: Option<TablePreset>,
+}
+
+#[derive(Debug, Clone)]
+struct Cutoff {
+    vpip: f64,
+    // Hands scoring above this always play
+    threshold: f64,
+    // Chance of playing a hand scoring exactly `threshold`, so the play rate is exact
+    threshold_play: f64,
+}
+
+// How often players at a kind of table play their hands from each position: tighter in
+// early position, wider on the button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TablePreset {
+    // Nine or ten players who open about 10% under the gun and 36% on the button
+    FullRing,
+    // Six players who open about 17% under the gun and 45% on the button
+    SixMax,
+    // A home game that sees most flops from anywhere
+    Loose,
+}
+
+pub const TABLE_PRESETS: [TablePreset; 3] = [
+    TablePreset::FullRing,
+    TablePreset::SixMax,
+    TablePreset::Loose,
+];
+
+impl TablePreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TablePreset::FullRing => "full-ring",
+            TablePreset::SixMax => "6max",
+            TablePreset::Loose => "loose",
+        }
+    }
+
+    // Share of hands played from the small blind, then from the button and each seat
+    // further from it; seats further out than the list play like its last entry
+    fn rates(&self) -> (f64, &'static [f64]) {
+        match self {
+            TablePreset::FullRing => (0.28, &[0.36, 0.25, 0.18, 0.15, 0.13, 0.12, 0.11, 0.10]),
+            TablePreset::SixMax => (0.36, &[0.45, 0.28, 0.21, 0.17]),
+            TablePreset::Loose => (0.5, &[0.6, 0.5, 0.45, 0.4, 0.38, 0.36, 0.35]),
+        }
+    }
+
+    // Share of hands each seat plays, in seat order; the big blind plays them all
+    pub fn vpips(&self, num_players: usize) -> Vec<f64> {
+        let (small_blind, from_button) = self.rates();
+        (0..num_players)
+            .map(|seat| match seats_from_button(seat, num_players) {
+                Some(distance) => from_button[distance.min(from_button.len() - 1)],
+                None if seat + 1 == num_players => 1.0,
+                None => small_blind,
+            })
+            .collect()
+    }
+}
+
+// How many seats before the button `seat` sits, or None for the blinds. The last seat is
+// the big blind and the one before it the small blind, except heads-up, where the other
+// seat is the button.
+fn seats_from_button(seat: usize, num_players: usize) -> Option<usize> {
+    match num_players {
+        _ if seat + 1 == num_players => None,
+        2 => Some(0),
+        _ if seat + 2 == num_players => None,
+        _ => Some(num_players - 3 - seat),
+    }
+}
+
+// The usual name of a seat's position, e.g. "UTG", "CO" or "BB"
+pub fn position_name(seat: usize, num_players: usize) -> String {
+    match seats_from_button(seat, num_players) {
+        None if seat + 1 == num_players => "BB".to_string(),
+        None => "SB".to_string(),
+        Some(0) => "BTN".to_string(),
+        Some(1) => "CO".to_string(),
+        Some(2) => "HJ".to_string(),
+        Some(_) if seat == 0 => "UTG".to_string(),
+        Some(3) => "LJ".to_string(),
+        Some(_) => format!("UTG+{}", seat),
+    }
+}
+
+// Bill Chen's starting hand score, without the final rounding up so that more hands
+// keep distinct scores
+pub fn chen_score(hole_cards: &[Card]) -> f64 {
+    let high = hole_cards[0].rank.max(hole_cards[1].rank);
+    let low = hole_cards[0].rank.min(hole_cards[1].rank);
+    let high_points = match high {
+        Rank::Ace => 10.0,
+        Rank::King => 8.0,
+        Rank::Queen => 7.0,
+        Rank::Jack => 6.0,
+        rank => rank as u8 as f64 / 2.0,
+    };
+    if high == low {
+        return (high_points * 2.0).max(5.0);
+    }
+
+    let mut score = high_points;
+    if hole_cards[0].suit == hole_cards[1].suit {
+        score += 2.0;
+    }
+    let gap = high as u8 - low as u8 - 1;
+    score -= match gap {
+        0 => 0.0,
+        1 => 1.0,
+        2 => 2.0,
+        3 => 4.0,
+        _ => 5.0,
+    };
+    // Connected low cards can make more straights
+    if gap <= 1 && high < Rank::Queen {
+        score += 1.0;
+    }
+    score
+}
+
+// Chen scores of all 1326 starting hands, best first
+fn sorted_scores() -> Vec<f64> {
+    let mut deck = Deck::new();
+    let cards: Vec<Card> = std::iter::from_fn(|| deck.deal()).collect();
+    let mut scores: Vec<f64> = cards
+        .iter()
+        .tuple_combinations()
+        .map(|(&a, &b)| chen_score(&[a, b]))
+        .collect();
+    scores.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    scores
+}
+
+impl Cutoff {
+    fn new(vpip: f64, scores: &[f64]) -> Cutoff {
+        // Play everything above the score of the last hand inside the target share, and
+        // hands tied with it just often enough to make up the rest
+        let target = vpip * scores.len() as f64;
+        let last = (target.ceil() as usize).clamp(1, scores.len()) - 1;
+        let threshold = scores[last];
+        let above = scores.iter().filter(|&&score| score > threshold).count() as f64;
+        let tied = scores.iter().filter(|&&score| score == threshold).count() as f64;
+        Cutoff {
+            vpip,
+            threshold,
+            threshold_play: ((target - above) / tied).clamp(0.0, 1.0),
+        }
+    }
+
+    fn plays<R: Rng>(&self, hole_cards: &[Card], rng: &mut R) -> bool {
+        let score = chen_score(hole_cards);
+        score > self.threshold || (score == self.threshold && rng.gen_bool(self.threshold_play))
+    }
+}
+
+impl FoldModel {
+    // `vpip` is the share of hands each seat plays, in (0, 1]
+    pub fn new(vpip: f64) -> FoldModel {
+        FoldModel {
+            cutoffs: vec![Cutoff::new(vpip, &sorted_scores())],
+            table: None,
+        }
+    }
+
+    // Each seat plays as often as `table` has players in its position play
+    pub fn for_table(table: TablePreset, num_players: usize) -> FoldModel {
+        let scores = sorted_scores();
+        FoldModel {
+            cutoffs: table
+                .vpips(num_players)
+                .into_iter()
+                .map(|vpip| Cutoff::new(vpip, &scores))
+                .collect(),
+            table: Some(table),
+        }
+    }
+
+    pub fn table(&self) -> Option<TablePreset> {
+        self.table
+    }
+
+    // Share of hands `seat` plays
+    pub fn vpip(&self, seat: usize) -> f64 {
+        self.cutoff(seat).vpip
+    }
+
+    fn cutoff(&self, seat: usize) -> &Cutoff {
+        match self.table {
+            Some(_) => &self.cutoffs[seat],
+            None => &self.cutoffs[0],
+        }
+    }
+
+    // Which seats fold, given every seat's hole cards in seat order
+    pub fn folds<'a, I, R>(&self, hands: I, rng: &mut R) -> Vec<bool>
+    where
+        I: ExactSizeIterator<Item = &'a [Card]>,
+        R: Rng,
+    {
+        let big_blind = hands.len() - 1;
+        hands
+            .enumerate()
+            .map(|(seat, hole_cards)| {
+                seat != big_blind && !self.cutoff(seat).plays(hole_cards, rng)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for TablePreset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for TablePreset {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<TablePreset, String> {
+        TABLE_PRESETS
+            .iter()
+            .find(|table| table.name().eq_ignore_ascii_case(text))
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = TABLE_PRESETS.iter().map(TablePreset::name).collect();
+                format!(
+                    "unknown table preset {}; expected one of {}",
+                    text,
+                    names.join(", ")
+                )
+            })
+    }
+}
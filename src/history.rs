@@ -0,0 +1,53 @@
+use crate::notation::{cards_to_string, describe_hand};
+use crate::GameResult;
+
+// Render a finished game as a plain-text hand history
+pub fn format_hand_history(hand_number: usize, result: &GameResult) -> String {
+    let mut out = format!("Hand #{}\n", hand_number);
+    if !result.board.is_empty() {
+        out.push_str(&format!("  Board: {}\n", cards_to_string(&result.board)));
+    }
+    for (i, hole_cards) in result.hole_cards.iter().enumerate() {
+        let best_five = result.variant.best_five(hole_cards, &result.board);
+        let low = match result.low_hands[i] {
+            Some(low) => format!(", low {}", low),
+            None => String::new(),
+        };
+        let folded = if result.folded[i] { ", folded" } else { "" };
+        let discarded = match &result.discarded[i][..] {
+            [] => String::new(),
+            cards => format!(" (discarded {})", cards_to_string(cards)),
+        };
+        out.push_str(&format!(
+            "  Seat {}: {}{}  {}{}{}\n",
+            i + 1,
+            cards_to_string(hole_cards),
+            discarded,
+            describe_hand(&result.hand_ranks[i], &best_five),
+            low,
+            folded
+        ));
+    }
+    if !result.variant.is_hi_lo() {
+        out.push_str(&format!("  {}\n", describe_winners(&result.winners, None)));
+    } else if result.low_winners.is_empty() {
+        let winners = describe_winners(&result.winners, None);
+        out.push_str(&format!("  {}; no qualifying low\n", winners));
+    } else {
+        let high = describe_winners(&result.winners, Some("high"));
+        let low = describe_winners(&result.low_winners, Some("low"));
+        out.push_str(&format!("  {}\n  {}\n", high, low));
+    }
+    out
+}
+
+// "Seat 2 wins", "Seats 1, 3 split the pot", or the same for one half of a hi-lo pot
+fn describe_winners(winners: &[usize], half: Option<&str>) -> String {
+    let seats: Vec<String> = winners.iter().map(|&seat| (seat + 1).to_string()).collect();
+    match (winners.len(), half) {
+        (1, None) => format!("Seat {} wins", seats[0]),
+        (1, Some(half)) => format!("Seat {} wins {}", seats[0], half),
+        (_, None) => format!("Seats {} split the pot", seats.join(", ")),
+        (_, Some(half)) => format!("Seats {} split {}", seats.join(", "), half),
+    }
+}
@@ -0,0 +1,81 @@
+use crate::filter::HandFilter;
+use crate::history::format_hand_history;
+use crate::reservoir::Reservoir;
+use crate::rng::{task_rng, Purpose};
+use crate::GameResult;
+use std::sync::Mutex;
+
+// Called with the full result of every completed game, so custom awards, promotions
+// or metrics can be computed without touching the simulation loop. Hooks are shared
+// by all worker threads, so any state they keep needs interior mutability.
+pub trait HandHook: Sync {
+    fn on_hand(&self, hand_number: usize, result: &GameResult);
+
+    // Called once after the last game, e.g. to print a summary
+    fn finish(&mut self) {}
+}
+
+// Prints every hand matching a filter as it is played
+pub struct LogHook {
+    filter: HandFilter,
+}
+
+impl LogHook {
+    pub fn new(filter: HandFilter) -> LogHook {
+        LogHook { filter }
+    }
+}
+
+impl HandHook for LogHook {
+    fn on_hand(&self, hand_number: usize, result: &GameResult) {
+        if self.filter.matches(result) {
+            print!("{}", format_hand_history(hand_number, result));
+        }
+    }
+}
+
+// Keeps a uniform sample of the hands matching a filter and prints them at the end
+pub struct SampleHook {
+    filter: HandFilter,
+    sample_size: usize,
+    seed: u64,
+    samples: Mutex<Reservoir<(usize, GameResult)>>,
+}
+
+impl SampleHook {
+    pub fn new(filter: HandFilter, sample_size: usize, seed: u64) -> SampleHook {
+        SampleHook {
+            filter,
+            sample_size,
+            seed,
+            samples: Mutex::new(Reservoir::new(sample_size)),
+        }
+    }
+}
+
+impl HandHook for SampleHook {
+    fn on_hand(&self, hand_number: usize, result: &GameResult) {
+        // The lock is only taken for the rare matches
+        if self.filter.matches(result) {
+            // Seeded by hand number, so the same hands are sampled on every thread count
+            let mut rng = task_rng(self.seed, Purpose::Sample, hand_number as u64);
+            let mut samples_lock = self.samples.lock().unwrap();
+            samples_lock.offer((hand_number, result.clone()), &mut rng);
+        }
+    }
+
+    fn finish(&mut self) {
+        let samples = std::mem::replace(self.samples.get_mut().unwrap(), Reservoir::new(0));
+        println!(
+            "\nSampled hands matching {} ({} of {} matches):",
+            self.filter.source(),
+            samples.seen().min(self.sample_size),
+            samples.seen()
+        );
+        let mut sampled = samples.into_items();
+        sampled.sort_by_key(|(hand_number, _)| *hand_number);
+        for (hand_number, result) in &sampled {
+            print!("\n{}", format_hand_history(*hand_number, result));
+        }
+    }
+}
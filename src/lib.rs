@@ -0,0 +1,485 @@
+// Texas Hold'em hand evaluation and Monte Carlo simulation. The `pokersim` binary is a
+// command-line front end over this library.
+
+pub mod aggregate;
+pub mod alloc_counter;
+pub mod batch;
+pub mod bitboard;
+pub mod budget;
+pub mod cache;
+pub mod callers;
+pub mod canonical;
+pub mod chunk_log;
+pub mod combinatorics;
+pub mod composition;
+pub mod config;
+pub mod constraint;
+pub mod dataset;
+pub mod decided;
+pub mod draw;
+pub mod equity;
+pub mod filter;
+pub mod folding;
+pub mod history;
+pub mod hooks;
+pub mod line;
+pub mod lookup;
+pub mod low;
+pub mod notation;
+pub mod profile;
+pub mod progress;
+pub mod promo;
+pub mod query;
+pub mod range;
+pub mod reference;
+#[cfg(feature = "json")]
+pub mod report;
+pub mod reservoir;
+pub mod rng;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod simulator;
+pub mod sketch;
+pub mod streets;
+pub mod transitions;
+pub mod variant;
+
+use itertools::Itertools;
+use low::LowHandRank;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use variant::Variant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rank {
+    Two = 2,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Card {
+    pub rank: Rank,
+    pub suit: Suit,
+}
+
+impl Card {
+    pub fn new(rank: Rank, suit: Suit) -> Self {
+        Card { rank, suit }
+    }
+
+    // Position of the card in a fresh deck, 0..52
+    pub fn index(&self) -> usize {
+        self.suit as usize * 13 + (self.rank as usize - 2)
+    }
+}
+
+pub struct Deck {
+    cards: Vec<Card>,
+}
+
+impl Deck {
+    pub fn new() -> Self {
+        let mut cards = Vec::with_capacity(52);
+        for &suit in &[Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades] {
+            for rank_value in 2..=14 {
+                let rank = match rank_value {
+                    2 => Rank::Two,
+                    3 => Rank::Three,
+                    4 => Rank::Four,
+                    5 => Rank::Five,
+                    6 => Rank::Six,
+                    7 => Rank::Seven,
+                    8 => Rank::Eight,
+                    9 => Rank::Nine,
+                    10 => Rank::Ten,
+                    11 => Rank::Jack,
+                    12 => Rank::Queen,
+                    13 => Rank::King,
+                    14 => Rank::Ace,
+                    _ => unreachable!(),
+                };
+                cards.push(Card::new(rank, suit));
+            }
+        }
+        Deck { cards }
+    }
+
+    // A deck of exactly `cards`, in that order
+    pub fn from_cards(cards: Vec<Card>) -> Self {
+        Deck { cards }
+    }
+
+    // The 36-card Short Deck (6+) pack, twos through fives removed
+    pub fn short() -> Self {
+        let mut deck = Deck::new();
+        deck.cards.retain(|card| card.rank >= Rank::Six);
+        deck
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
+    }
+
+    pub fn deal(&mut self) -> Option<Card> {
+        self.cards.pop()
+    }
+
+    // Take a known card out of the deck, e.g. a player's fixed hole cards
+    pub fn remove(&mut self, card: &Card) {
+        self.cards.retain(|other| other.index() != card.index());
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Deck::new()
+    }
+}
+
+// Full outcome of one simulated game; all statistics are aggregated from these
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameResult {
+    // The rules the hands were evaluated under
+    pub variant: Variant,
+    // The hole cards each player played, after any discards
+    pub hole_cards: Vec<Vec<Card>>,
+    // Cards each player threw away, empty in games without a discard
+    pub discarded: Vec<Vec<Card>>,
+    pub board: Vec<Card>,
+    // Each player's best hand
+    pub hand_ranks: Vec<HandRank>,
+    // Players who folded preflop; their hands are still evaluated but cannot win
+    pub folded: Vec<bool>,
+    // Every player still in holding the best hand; they split the pot evenly
+    pub winners: Vec<usize>,
+    // Each player's qualifying low in hi-lo games
+    pub low_hands: Vec<Option<LowHandRank>>,
+    // Every player still in holding the best low; they split the low half of the pot and
+    // the high hands take it all when nobody has a low
+    pub low_winners: Vec<usize>,
+}
+
+impl GameResult {
+    pub fn from_showdown(
+        variant: Variant,
+        hole_cards: Vec<Vec<Card>>,
+        board: Vec<Card>,
+        hand_ranks: Vec<HandRank>,
+        folded: Vec<bool>,
+    ) -> Self {
+        // Find every player still in holding the best hand
+        let strengths: Vec<_> = hand_ranks
+            .iter()
+            .map(|hand_rank| variant.strength(hand_rank))
+            .collect();
+        let best_strength = (0..hand_ranks.len())
+            .filter(|&i| !folded[i])
+            .map(|i| strengths[i])
+            .max()
+            .unwrap();
+        let winner_indices: Vec<usize> = (0..hand_ranks.len())
+            .filter(|&i| !folded[i] && strengths[i] == best_strength)
+            .collect();
+
+        let low_hands: Vec<Option<LowHandRank>> = hole_cards
+            .iter()
+            .map(|hole_cards| variant.evaluate_low(hole_cards, &board))
+            .collect();
+        let best_low = (0..low_hands.len())
+            .filter(|&i| !folded[i])
+            .filter_map(|i| low_hands[i])
+            .min();
+        let low_winners = match best_low {
+            Some(best_low) => (0..low_hands.len())
+                .filter(|&i| !folded[i] && low_hands[i] == Some(best_low))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        GameResult {
+            variant,
+            discarded: vec![Vec::new(); hole_cards.len()],
+            hole_cards,
+            board,
+            hand_ranks,
+            folded,
+            winners: winner_indices,
+            low_hands,
+            low_winners,
+        }
+    }
+
+    // Whether the pot is shared, between tied hands or between a high and a low hand
+    pub fn is_tie(&self) -> bool {
+        self.winners.len() > 1 || (!self.low_winners.is_empty() && self.low_winners != self.winners)
+    }
+
+    // The winning hand, shared by every winner
+    pub fn best_hand(&self) -> &HandRank {
+        &self.hand_ranks[self.winners[0]]
+    }
+
+    // Share of the pot won by `seat`
+    pub fn equity(&self, seat: usize) -> f64 {
+        let share = |winners: &[usize]| {
+            if winners.contains(&seat) {
+                1.0 / winners.len() as f64
+            } else {
+                0.0
+            }
+        };
+        if self.low_winners.is_empty() {
+            share(&self.winners)
+        } else {
+            (share(&self.winners) + share(&self.low_winners)) / 2.0
+        }
+    }
+
+    pub fn players_in(&self) -> usize {
+        self.folded.iter().filter(|&&folded| !folded).count()
+    }
+
+    pub fn categories(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.hand_ranks.iter().map(hand_rank_category)
+    }
+}
+
+// Variants compare by category first, then field by field, so every field after the
+// made hand is a kicker in the order it breaks ties
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandRank {
+    HighCard([Rank; 5]),
+    OnePair(Rank, [Rank; 3]),
+    TwoPair(Rank, Rank, Rank),
+    ThreeOfAKind(Rank, [Rank; 2]),
+    Straight(Rank),
+    Flush([Rank; 5]),
+    FullHouse(Rank, Rank),
+    FourOfAKind(Rank, Rank),
+    StraightFlush(Rank),
+    RoyalFlush,
+}
+
+// Function to map HandRank instances to their categories
+pub fn hand_rank_category(hand_rank: &HandRank) -> &'static str {
+    match hand_rank {
+        HandRank::HighCard(_) => "HighCard",
+        HandRank::OnePair(_, _) => "OnePair",
+        HandRank::TwoPair(_, _, _) => "TwoPair",
+        HandRank::ThreeOfAKind(_, _) => "ThreeOfAKind",
+        HandRank::Straight(_) => "Straight",
+        HandRank::Flush(_) => "Flush",
+        HandRank::FullHouse(_, _) => "FullHouse",
+        HandRank::FourOfAKind(_, _) => "FourOfAKind",
+        HandRank::StraightFlush(_) => "StraightFlush",
+        HandRank::RoyalFlush => "RoyalFlush",
+    }
+}
+
+fn is_sequence(mut ranks: Vec<u8>) -> bool {
+    ranks.sort_unstable();
+    ranks.dedup();
+
+    if ranks.len() != 5 {
+        return false;
+    }
+
+    let is_regular_straight = ranks[4] - ranks[0] == 4;
+
+    // Special case for wheel straight (A-2-3-4-5)
+    let is_wheel_straight = ranks == vec![2, 3, 4, 5, 14];
+
+    is_regular_straight || is_wheel_straight
+}
+
+fn get_rank_counts(ranks: &[Rank]) -> HashMap<Rank, u8> {
+    let mut counts = HashMap::new();
+    for &rank in ranks {
+        *counts.entry(rank).or_insert(0) += 1;
+    }
+    counts
+}
+
+pub fn evaluate_five_card_hand(cards: &[&Card]) -> HandRank {
+    let mut ranks: Vec<Rank> = cards.iter().map(|c| c.rank).collect();
+    let mut rank_values: Vec<u8> = ranks.iter().map(|&r| r as u8).collect();
+    ranks.sort_by(|a, b| b.cmp(a)); // Sort descending
+    rank_values.sort_unstable();
+    rank_values.dedup();
+
+    let suits: Vec<Suit> = cards.iter().map(|c| c.suit).collect();
+
+    let is_flush = suits.iter().all(|&s| s == suits[0]);
+
+    let is_straight = is_sequence(rank_values.clone());
+
+    // The ace plays low in a wheel, making it a five-high straight
+    let straight_high = if ranks[0] == Rank::Ace && ranks[1] == Rank::Five {
+        Rank::Five
+    } else {
+        ranks[0]
+    };
+
+    if is_flush && is_straight {
+        if straight_high == Rank::Ace {
+            return HandRank::RoyalFlush;
+        } else {
+            return HandRank::StraightFlush(straight_high);
+        }
+    }
+
+    let rank_counts = get_rank_counts(&ranks);
+
+    // Distinct ranks appearing exactly `count` times, highest first
+    let ranks_with_count = |count: u8| -> Vec<Rank> {
+        ranks
+            .iter()
+            .copied()
+            .filter(|rank| rank_counts[rank] == count)
+            .dedup()
+            .collect()
+    };
+    let quads = ranks_with_count(4);
+    let trips = ranks_with_count(3);
+    let pairs = ranks_with_count(2);
+    let singles = ranks_with_count(1);
+
+    if let Some(&quad_rank) = quads.first() {
+        return HandRank::FourOfAKind(quad_rank, singles[0]);
+    }
+
+    if let (Some(&three_rank), Some(&two_rank)) = (trips.first(), pairs.first()) {
+        return HandRank::FullHouse(three_rank, two_rank);
+    }
+
+    if is_flush {
+        return HandRank::Flush([ranks[0], ranks[1], ranks[2], ranks[3], ranks[4]]);
+    }
+
+    if is_straight {
+        return HandRank::Straight(straight_high);
+    }
+
+    if let Some(&three_rank) = trips.first() {
+        return HandRank::ThreeOfAKind(three_rank, [singles[0], singles[1]]);
+    }
+
+    if pairs.len() == 2 {
+        return HandRank::TwoPair(pairs[0], pairs[1], singles[0]);
+    } else if pairs.len() == 1 {
+        return HandRank::OnePair(pairs[0], [singles[0], singles[1], singles[2]]);
+    }
+
+    HandRank::HighCard([ranks[0], ranks[1], ranks[2], ranks[3], ranks[4]])
+}
+
+// The best hand together with the five cards that make it
+pub fn best_five_cards(cards: &[Card]) -> (HandRank, Vec<Card>) {
+    cards
+        .iter()
+        .combinations(5)
+        .map(|combo| (evaluate_five_card_hand(&combo), combo))
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(rank, combo)| (rank, combo.into_iter().copied().collect()))
+        .unwrap()
+}
+
+pub fn evaluate_hand(cards: &[Card]) -> HandRank {
+    cards
+        .iter()
+        .combinations(5)
+        .map(|combo| evaluate_five_card_hand(&combo))
+        .max()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_cards;
+    use Rank::*;
+
+    fn five(text: &str) -> HandRank {
+        let cards = parse_cards(text).unwrap();
+        evaluate_five_card_hand(&cards.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn high_card_kickers() {
+        assert_eq!(
+            five("2c Ks 9d Ah 7s"),
+            HandRank::HighCard([Ace, King, Nine, Seven, Two])
+        );
+        // Decided by the last card
+        assert!(five("As Kd 9c 7h 3s") > five("Ah Ks 9d 7s 2c"));
+        assert!(five("As Qd Jc 9h 8s") < five("Ah Ks 3d 4s 2c"));
+    }
+
+    #[test]
+    fn one_pair_kickers() {
+        assert_eq!(
+            five("As Ad Kc 7h 2s"),
+            HandRank::OnePair(Ace, [King, Seven, Two])
+        );
+        assert!(five("As Ad Kc 7h 2s") > five("Ah Ac Qd Js 9h"));
+        assert!(five("As Ad Kc 7h 3s") > five("Ah Ac Kd 7s 2h"));
+        assert!(five("2s 2d Ac Kh Qs") < five("3h 3c 4d 5s 6h"));
+    }
+
+    #[test]
+    fn two_pair_kickers() {
+        assert_eq!(
+            five("Ks Kd 3c Qh Qs"),
+            HandRank::TwoPair(King, Queen, Three)
+        );
+        // The fifth card breaks a tie between the same two pairs
+        assert!(five("Ks Kd Qc Qh 4s") > five("Kh Kc Qd Qs 3h"));
+        assert_eq!(five("Ks Kd Qc Qh 4s"), five("Kh Kc Qd Qs 4h"));
+        // The top pair counts before the second pair, and the second before the kicker
+        assert!(five("Ks Kd 2c 2h 3s") > five("Qh Qc Jd Js Ah"));
+        assert!(five("Ks Kd Jc Jh 2s") > five("Kh Kc Td Ts Ah"));
+    }
+
+    #[test]
+    fn trips_kickers() {
+        assert_eq!(
+            five("7s 7d 2c Kh 7c"),
+            HandRank::ThreeOfAKind(Seven, [King, Two])
+        );
+        assert!(five("7s 7d 7c Kh 2s") > five("7h 7d 7s Qd Jc"));
+        assert!(five("7s 7d 7c Kh 3s") > five("7h 7d 7s Kd 2c"));
+        assert!(five("8s 8d 8c 2h 3s") > five("7h 7d 7s Ad Kc"));
+    }
+
+    #[test]
+    fn quads_kickers() {
+        assert_eq!(five("9s 9d 9c 9h As"), HandRank::FourOfAKind(Nine, Ace));
+        assert!(five("9s 9d 9c 9h As") > five("9s 9d 9c 9h Ks"));
+        assert!(five("Ts Td Tc Th 2s") > five("9s 9d 9c 9h As"));
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Compact action-line notation, e.g. "r2.5 c/x b33 c/x x".
+// Streets are separated by '/', actions by optional whitespace:
+//   f fold, x check, c call, b<size> bet, r<size> raise to
+// Preflop sizes are in big blinds, postflop sizes in percent of the pot.
+
+const STREETS: [&str; 4] = ["Preflop", "Flop", "Turn", "River"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Fold,
+    Check,
+    Call,
+    Bet(f64),
+    Raise(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    streets: Vec<Vec<Action>>,
+}
+
+impl Line {
+    // One readable line per street, with sizes in the street's unit
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        for (i, actions) in self.streets.iter().enumerate() {
+            let unit = if i == 0 { "bb" } else { "% pot" };
+            let words: Vec<String> = actions
+                .iter()
+                .map(|action| match action {
+                    Action::Fold => "fold".to_string(),
+                    Action::Check => "check".to_string(),
+                    Action::Call => "call".to_string(),
+                    Action::Bet(size) => format!("bet {}{}", size, unit),
+                    Action::Raise(size) => format!("raise to {}{}", size, unit),
+                })
+                .collect();
+            out.push_str(&format!("{}: {}\n", STREETS[i], words.join(", ")));
+        }
+        out
+    }
+}
+
+impl FromStr for Line {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Line, String> {
+        let streets: Vec<&str> = text.trim().split('/').collect();
+        if streets.len() > STREETS.len() {
+            return Err(format!("a line has at most {} streets", STREETS.len()));
+        }
+        let streets = streets
+            .into_iter()
+            .map(parse_street)
+            .collect::<Result<_, _>>()?;
+        Ok(Line { streets })
+    }
+}
+
+fn parse_street(text: &str) -> Result<Vec<Action>, String> {
+    let mut actions = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let action = match c {
+            c if c.is_whitespace() => continue,
+            'f' => Action::Fold,
+            'x' => Action::Check,
+            'c' => Action::Call,
+            'b' | 'r' => {
+                let mut size = String::new();
+                while let Some(&d) = chars.peek() {
+                    if !(d.is_ascii_digit() || d == '.') {
+                        break;
+                    }
+                    size.push(d);
+                    chars.next();
+                }
+                let size: f64 = size
+                    .parse()
+                    .map_err(|_| format!("'{}' needs a size, e.g. {}2.5", c, c))?;
+                if c == 'b' {
+                    Action::Bet(size)
+                } else {
+                    Action::Raise(size)
+                }
+            }
+            _ => return Err(format!("unknown action '{}'", c)),
+        };
+        actions.push(action);
+    }
+    if actions.is_empty() {
+        return Err("empty street in line".to_string());
+    }
+    Ok(actions)
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Fold => write!(f, "f"),
+            Action::Check => write!(f, "x"),
+            Action::Call => write!(f, "c"),
+            Action::Bet(size) => write!(f, "b{}", size),
+            Action::Raise(size) => write!(f, "r{}", size),
+        }
+    }
+}
+
+// Canonical form: actions separated by single spaces
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let streets: Vec<String> = self
+            .streets
+            .iter()
+            .map(|actions| {
+                actions
+                    .iter()
+                    .map(Action::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        write!(f, "{}", streets.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(text: &str) -> String {
+        text.parse::<Line>().unwrap().to_string()
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(normalize("r2.5c/x b33 c"), "r2.5 c/x b33 c");
+        assert_eq!(normalize("  r3 r9  c / xb75c/xx "), "r3 r9 c/x b75 c/x x");
+        for line in [
+            "r2.5 c/x b33 c",
+            "f",
+            "x x/b50 r150 f",
+            "r2 c/x x/x x/b100 c",
+        ] {
+            assert_eq!(normalize(line), line);
+            let parsed: Line = line.parse().unwrap();
+            assert_eq!(parsed.to_string().parse::<Line>().unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn describe() {
+        let line: Line = "r2.5c/x b33 c".parse().unwrap();
+        assert_eq!(
+            line.describe(),
+            "Preflop: raise to 2.5bb, call\nFlop: check, bet 33% pot, call\n"
+        );
+    }
+
+    #[test]
+    fn bad_tokens() {
+        let error = |text: &str| text.parse::<Line>().unwrap_err();
+        assert_eq!(error("r2.5 k"), "unknown action 'k'");
+        assert_eq!(error("b c"), "'b' needs a size, e.g. b2.5");
+        assert_eq!(error("r1.2.3"), "'r' needs a size, e.g. r2.5");
+        assert_eq!(error("r2 c//x"), "empty street in line");
+        assert_eq!(error(""), "empty street in line");
+        assert_eq!(error("c/x/x/x/x"), "a line has at most 4 streets");
+    }
+}
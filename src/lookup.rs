@@ -0,0 +1,382 @@
+use crate::bitboard::CardSet;
+use crate::notation::{RANKS, SUITS};
+use crate::{Card, HandRank, Rank};
+use std::sync::OnceLock;
+
+// Five- to seven-card evaluation without enumerating five-card subsets. The suit masks of
+// a CardSet give flushes by popcount; straights and the top five ranks of any mask come
+// from tables built on first use, and pairs, trips and quads fall out of adding the four
+// suit masks together bit by bit.
+
+// A value is the category's place in the ranking, the category itself, then fields that
+// each hold a rank (2-14), most significant first, laid out like the fields of the
+// matching HandRank variant. The place equals the category except where a game ranks
+// categories differently, as Short Deck does with flushes over full houses.
+const HIGH_CARD: u32 = 0;
+const ONE_PAIR: u32 = 1;
+const TWO_PAIR: u32 = 2;
+const THREE_OF_A_KIND: u32 = 3;
+const STRAIGHT: u32 = 4;
+const FLUSH: u32 = 5;
+const FULL_HOUSE: u32 = 6;
+const FOUR_OF_A_KIND: u32 = 7;
+const STRAIGHT_FLUSH: u32 = 8;
+const ROYAL_FLUSH: u32 = 9;
+
+// Strength of a hand; a higher value is a better hand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandValue(u32);
+
+// Place of each category in Short Deck, where a flush is harder to make than a full house
+const SHORT_DECK_ORDER: [u32; 10] = [0, 1, 2, 3, 4, 6, 5, 7, 8, 9];
+
+impl HandValue {
+    fn new(category: u32, fields: u32) -> HandValue {
+        HandValue(category << 24 | category << 20 | fields)
+    }
+
+    // Same numbering as filter::category_value
+    pub fn category(&self) -> u32 {
+        (self.0 >> 20) & 0xf
+    }
+
+    // Strength of a hand evaluated elsewhere, ranked like the values this module computes
+    pub fn from_hand_rank(hand_rank: &HandRank) -> HandValue {
+        let fields = |ranks: &[Rank]| {
+            ranks.iter().enumerate().fold(0, |fields, (i, &rank)| {
+                fields | (rank as u32) << (16 - 4 * i)
+            })
+        };
+        match *hand_rank {
+            HandRank::HighCard(ranks) => HandValue::new(HIGH_CARD, fields(&ranks)),
+            HandRank::OnePair(pair, kickers) => HandValue::new(
+                ONE_PAIR,
+                fields(&[pair, kickers[0], kickers[1], kickers[2]]),
+            ),
+            HandRank::TwoPair(high, low, kicker) => {
+                HandValue::new(TWO_PAIR, fields(&[high, low, kicker]))
+            }
+            HandRank::ThreeOfAKind(trips, kickers) => {
+                HandValue::new(THREE_OF_A_KIND, fields(&[trips, kickers[0], kickers[1]]))
+            }
+            HandRank::Straight(high) => HandValue::new(STRAIGHT, fields(&[high])),
+            HandRank::Flush(ranks) => HandValue::new(FLUSH, fields(&ranks)),
+            HandRank::FullHouse(trips, pair) => HandValue::new(FULL_HOUSE, fields(&[trips, pair])),
+            HandRank::FourOfAKind(quads, kicker) => {
+                HandValue::new(FOUR_OF_A_KIND, fields(&[quads, kicker]))
+            }
+            HandRank::StraightFlush(high) => HandValue::new(STRAIGHT_FLUSH, fields(&[high])),
+            HandRank::RoyalFlush => HandValue::new(ROYAL_FLUSH, 0),
+        }
+    }
+
+    // The same hand ranked by Short Deck rules
+    pub fn to_short_deck(self) -> HandValue {
+        let place = SHORT_DECK_ORDER[self.category() as usize];
+        HandValue(place << 24 | (self.0 & 0xff_ffff))
+    }
+
+    pub fn to_hand_rank(&self) -> HandRank {
+        let rank = |field: u32| RANKS[((self.0 >> (16 - 4 * field)) & 0xf) as usize - 2];
+        match self.category() {
+            HIGH_CARD => HandRank::HighCard([rank(0), rank(1), rank(2), rank(3), rank(4)]),
+            ONE_PAIR => HandRank::OnePair(rank(0), [rank(1), rank(2), rank(3)]),
+            TWO_PAIR => HandRank::TwoPair(rank(0), rank(1), rank(2)),
+            THREE_OF_A_KIND => HandRank::ThreeOfAKind(rank(0), [rank(1), rank(2)]),
+            STRAIGHT => HandRank::Straight(rank(0)),
+            FLUSH => HandRank::Flush([rank(0), rank(1), rank(2), rank(3), rank(4)]),
+            FULL_HOUSE => HandRank::FullHouse(rank(0), rank(1)),
+            FOUR_OF_A_KIND => HandRank::FourOfAKind(rank(0), rank(1)),
+            STRAIGHT_FLUSH => HandRank::StraightFlush(rank(0)),
+            _ => HandRank::RoyalFlush,
+        }
+    }
+}
+
+impl From<HandValue> for HandRank {
+    fn from(value: HandValue) -> HandRank {
+        value.to_hand_rank()
+    }
+}
+
+struct Tables {
+    // Rank of the highest straight in a rank mask, 0 when there is none
+    straights: Vec<u8>,
+    // The same for Short Deck, where A-6-7-8-9 is the lowest straight
+    short_deck_straights: Vec<u8>,
+    // The same with the ace only playing high, so A-2-3-4-5 is no straight
+    ace_high_straights: Vec<u8>,
+    // The five highest ranks of a rank mask as value fields, zero-filled
+    top_five: Vec<u32>,
+}
+
+impl Tables {
+    fn build() -> Tables {
+        let masks = 0..1u32 << 13;
+        let straights = masks
+            .clone()
+            .map(|mask| {
+                let high = (4..13)
+                    .rev()
+                    .find(|&high| (mask >> (high - 4)) & 0x1f == 0x1f);
+                match high {
+                    Some(high) => high as u8 + 2,
+                    // The wheel, A-2-3-4-5
+                    None if mask & 0x100f == 0x100f => Rank::Five as u8,
+                    None => 0,
+                }
+            })
+            .collect::<Vec<u8>>();
+        let short_deck_straights = masks
+            .clone()
+            .map(|mask| match straights[mask as usize] {
+                0 if mask & 0x10f0 == 0x10f0 => Rank::Nine as u8,
+                high => high,
+            })
+            .collect();
+        let ace_high_straights = masks
+            .clone()
+            .map(|mask| match straights[mask as usize] {
+                5 => 0,
+                high => high,
+            })
+            .collect();
+        let top_five = masks
+            .map(|mask| {
+                (0..13)
+                    .rev()
+                    .filter(|bit| mask & (1 << bit) != 0)
+                    .take(5)
+                    .enumerate()
+                    .fold(0, |fields, (i, bit)| fields | (bit + 2) << (16 - 4 * i))
+            })
+            .collect();
+        Tables {
+            straights,
+            short_deck_straights,
+            ace_high_straights,
+            top_five,
+        }
+    }
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+fn high_bit(mask: u32) -> u32 {
+    31 - mask.leading_zeros()
+}
+
+pub fn evaluate_seven(cards: &[Card; 7]) -> HandValue {
+    evaluate(cards.iter().copied().collect())
+}
+
+// The best five-card hand among five to seven cards
+pub fn evaluate(cards: CardSet) -> HandValue {
+    let tables = TABLES.get_or_init(Tables::build);
+    evaluate_with(cards, &tables.straights, tables)
+}
+
+// The best five-card hand among five to seven cards of a Short Deck
+pub fn evaluate_short_deck(cards: CardSet) -> HandValue {
+    let tables = TABLES.get_or_init(Tables::build);
+    evaluate_with(cards, &tables.short_deck_straights, tables).to_short_deck()
+}
+
+// The best five-card hand with the ace only playing high, as deuce-to-seven lowball
+// ranks hands
+pub fn evaluate_ace_high(cards: CardSet) -> HandValue {
+    let tables = TABLES.get_or_init(Tables::build);
+    evaluate_with(cards, &tables.ace_high_straights, tables)
+}
+
+fn evaluate_with(cards: CardSet, straights: &[u8], tables: &Tables) -> HandValue {
+    let suits = SUITS.map(|suit| cards.suit_mask(suit));
+
+    // Seven cards hold at most one flush, and a flush beats anything but quads and full
+    // houses, which need four different suits among the other cards
+    for &suited in &suits {
+        if suited.count_ones() >= 5 {
+            return match straights[suited as usize] as u32 {
+                0 => HandValue::new(FLUSH, tables.top_five[suited as usize]),
+                14 => HandValue::new(ROYAL_FLUSH, 0),
+                high => HandValue::new(STRAIGHT_FLUSH, high << 16),
+            };
+        }
+    }
+
+    // How many of each rank, as a three-bit count spread over three masks
+    let [clubs, diamonds, hearts, spades] = suits;
+    let (low_sum, low_carry) = (clubs ^ diamonds, clubs & diamonds);
+    let (high_sum, high_carry) = (hearts ^ spades, hearts & spades);
+    let ones = low_sum ^ high_sum;
+    let twos = low_carry ^ high_carry ^ (low_sum & high_sum);
+    let fours = low_carry & high_carry;
+
+    let any = clubs | diamonds | hearts | spades;
+    let singles = ones & !twos;
+    let pairs = twos & !ones;
+    let trips = ones & twos;
+    let rank = |bit: u32| bit + 2;
+
+    if fours != 0 {
+        let quads = high_bit(fours);
+        let kicker = high_bit(any & !(1 << quads));
+        return HandValue::new(FOUR_OF_A_KIND, rank(quads) << 16 | rank(kicker) << 12);
+    }
+    if trips != 0 && (trips.count_ones() > 1 || pairs != 0) {
+        let set = high_bit(trips);
+        // A second set of trips plays as the pair
+        let pair = high_bit((trips & !(1 << set)) | pairs);
+        return HandValue::new(FULL_HOUSE, rank(set) << 16 | rank(pair) << 12);
+    }
+    match straights[any as usize] as u32 {
+        0 => {}
+        high => return HandValue::new(STRAIGHT, high << 16),
+    }
+    if trips != 0 {
+        let kickers = (tables.top_five[singles as usize] >> 4) & 0xff00;
+        return HandValue::new(THREE_OF_A_KIND, rank(high_bit(trips)) << 16 | kickers);
+    }
+    if pairs.count_ones() > 1 {
+        let high = high_bit(pairs);
+        let low = high_bit(pairs & !(1 << high));
+        // The third pair, if any, can play as the kicker
+        let kicker = high_bit(any & !(1 << high) & !(1 << low));
+        return HandValue::new(
+            TWO_PAIR,
+            rank(high) << 16 | rank(low) << 12 | rank(kicker) << 8,
+        );
+    }
+    if pairs != 0 {
+        let kickers = (tables.top_five[singles as usize] >> 4) & 0xfff0;
+        return HandValue::new(ONE_PAIR, rank(high_bit(pairs)) << 16 | kickers);
+    }
+    HandValue::new(HIGH_CARD, tables.top_five[any as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_cards;
+    use crate::rng::{task_rng, Purpose};
+    use crate::variant::Variant;
+    use crate::{evaluate_hand, Deck};
+    use itertools::Itertools;
+
+    fn check(cards: &[Card]) -> HandValue {
+        let value = evaluate(CardSet::from(cards));
+        assert_eq!(value.to_hand_rank(), evaluate_hand(cards), "{:?}", cards);
+        assert_eq!(value, HandValue::from_hand_rank(&evaluate_hand(cards)));
+        value
+    }
+
+    fn hand(text: &str) -> HandRank {
+        let cards = parse_cards(text).unwrap();
+        check(&cards).to_hand_rank()
+    }
+
+    #[test]
+    fn every_rank_class_matches_evaluate_hand() {
+        // Each multiset of five ranks, suits dealt in turn so it is no flush, and each set
+        // of five distinct ranks in one suit: the 7,462 classes of five-card hands
+        let mut classes = Vec::new();
+        for ranks in RANKS.iter().combinations_with_replacement(5) {
+            if ranks.iter().dedup_with_count().any(|(count, _)| count > 4) {
+                continue;
+            }
+            let mixed: Vec<Card> = ranks
+                .iter()
+                .enumerate()
+                .map(|(i, &&rank)| Card::new(rank, SUITS[i % 4]))
+                .collect();
+            classes.push((evaluate_hand(&mixed), check(&mixed)));
+            if ranks.iter().all_unique() {
+                let suited: Vec<Card> = ranks
+                    .iter()
+                    .map(|&&rank| Card::new(rank, SUITS[0]))
+                    .collect();
+                classes.push((evaluate_hand(&suited), check(&suited)));
+            }
+        }
+        assert_eq!(classes.len(), 7462);
+        // Both evaluators order the classes the same way, with no two of them equal
+        classes.sort();
+        assert!(classes.windows(2).all(|pair| pair[0].1 < pair[1].1));
+    }
+
+    #[test]
+    fn seeded_seven_card_hands_match_evaluate_hand() {
+        let mut rng = task_rng(261, Purpose::Deal, 0);
+        for _ in 0..5000 {
+            let mut deck = Deck::new();
+            deck.shuffle(&mut rng);
+            let cards: Vec<Card> = (0..7).map(|_| deck.deal().unwrap()).collect();
+            check(&cards);
+        }
+    }
+
+    #[test]
+    fn wheel_and_broadway() {
+        assert_eq!(hand("As 2d 3c 4h 5s"), HandRank::Straight(Rank::Five));
+        assert_eq!(hand("As 2d 3c 4h 5s 6d Kc"), HandRank::Straight(Rank::Six));
+        assert_eq!(hand("Ts Jd Qc Kh As"), HandRank::Straight(Rank::Ace));
+        assert_eq!(hand("9c Ts Jd Qc Kh As 2d"), HandRank::Straight(Rank::Ace));
+        assert_eq!(
+            hand("Ah 2h 3h 4h 5h Kd Qc"),
+            HandRank::StraightFlush(Rank::Five)
+        );
+        assert_eq!(hand("Th Jh Qh Kh Ah 9h 2c"), HandRank::RoyalFlush);
+        // An ace is no straight card around the corner
+        assert!(matches!(hand("Qs Kd Ac 2h 3s"), HandRank::HighCard(_)));
+    }
+
+    fn short(text: &str) -> HandValue {
+        evaluate_short_deck(CardSet::from(&parse_cards(text).unwrap()[..]))
+    }
+
+    #[test]
+    fn short_deck_straights() {
+        assert_eq!(
+            short("As 6d 7c 8h 9s").to_hand_rank(),
+            HandRank::Straight(Rank::Nine)
+        );
+        assert_eq!(
+            short("As 6d 7c 8h 9s Kd Kc").to_hand_rank(),
+            HandRank::Straight(Rank::Nine)
+        );
+        // The lowest straight, still above trips
+        assert!(short("As 6d 7c 8h 9s") < short("6d 7c 8h 9s Td"));
+        assert!(short("As 6d 7c 8h 9s") > short("As Ad Ac 8h 9s"));
+        assert_eq!(
+            short("Ah 6h 7h 8h 9h").to_hand_rank(),
+            HandRank::StraightFlush(Rank::Nine)
+        );
+        // No card of the 36 can fill in a wheel
+        let deck = crate::Deck::short();
+        assert_eq!(deck.cards().len(), 36);
+        assert!(deck.cards().iter().all(|card| card.rank >= Rank::Six));
+    }
+
+    #[test]
+    fn short_deck_flush_beats_full_house() {
+        let flush = short("Ah Th 8h 7h 6h Kd");
+        let full_house = short("As Ad Ac Ks Kd");
+        assert!(matches!(flush.to_hand_rank(), HandRank::Flush(_)));
+        assert_eq!(
+            full_house.to_hand_rank(),
+            HandRank::FullHouse(Rank::Ace, Rank::King)
+        );
+        assert!(flush > full_house);
+        assert!(short("Kh Th 9h 8h 6h") > short("As Ad Ac Ks Kd"));
+        assert!(short("As Ad Ac Ks Kd") < short("6s 6d 6c 6h 7s"));
+
+        let flush = HandRank::Flush([Rank::Ten, Rank::Nine, Rank::Eight, Rank::Seven, Rank::Six]);
+        let full_house = HandRank::FullHouse(Rank::Ace, Rank::King);
+        assert!(Variant::ShortDeck.strength(&flush) > Variant::ShortDeck.strength(&full_house));
+        assert!(Variant::Holdem.strength(&flush) < Variant::Holdem.strength(&full_house));
+        assert_eq!(
+            HandValue::from_hand_rank(&full_house).to_short_deck(),
+            short("As Ad Ac Ks Kd")
+        );
+    }
+}
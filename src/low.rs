@@ -0,0 +1,231 @@
+use crate::bitboard::CardSet;
+use crate::lookup::{evaluate_ace_high, HandValue};
+use crate::notation::{rank_to_char, RANKS};
+use crate::{Card, HandRank, Rank};
+use itertools::Itertools;
+use std::fmt;
+use std::str::FromStr;
+
+// Lowball comes in two systems. Ace-to-five (Razz, the low half of hi-lo games) plays the
+// ace low and ignores straights and flushes; deuce-to-seven (2-7 Triple Draw) plays the
+// ace high only and counts straights and flushes against the hand, so its best hand is
+// 7-5-4-3-2 in mixed suits. Each has its own type, ordered so that a lower value is a
+// better low, and the best low of more than five cards is the lowest five-card hand.
+
+// An ace-to-five low for the low half of a hi-lo pot: five cards of different ranks,
+// eight or lower, with the ace playing low and straights and flushes not counting against
+// it. The ranks are kept highest first with the ace as 1, so a lower value is a better
+// low: 7-5-4-3-2 beats 8-4-3-2-A.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LowHandRank([u8; 5]);
+
+// The best eight-or-better low among `cards`, if they hold one
+pub fn evaluate_low_eight(cards: CardSet) -> Option<LowHandRank> {
+    // Bit 0 for the ace up to bit 7 for the eight
+    let ranks = cards.rank_mask();
+    let mut low = (ranks & 0x7f) << 1 | (ranks >> 12) & 1;
+    if low.count_ones() < 5 {
+        return None;
+    }
+    let mut five = [0; 5];
+    for value in five.iter_mut().rev() {
+        let bit = low.trailing_zeros();
+        *value = bit as u8 + 1;
+        low &= low - 1;
+    }
+    Some(LowHandRank(five))
+}
+
+// An ace-to-five low of any five cards, pairs included: hands without a pair beat one
+// pair, which beats two pair and so on up to quads, and ties are broken rank by rank from
+// the most significant, as in 5-5-8-3-2 against 5-5-7-6-4
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AceFiveLow {
+    // 0 without a pair, then one pair, two pair, trips, full house and quads
+    pairing: u8,
+    // Grouped ranks first, larger groups first, then highest first, with the ace as 1
+    ranks: [u8; 5],
+}
+
+// The best ace-to-five low among five or more cards
+pub fn evaluate_low_a5(cards: CardSet) -> AceFiveLow {
+    cards
+        .iter()
+        .combinations(5)
+        .map(|five| ace_five(&five))
+        .min()
+        .unwrap()
+}
+
+fn ace_five(five: &[Card]) -> AceFiveLow {
+    let value = |card: &Card| match card.rank as u8 {
+        14 => 1,
+        rank => rank,
+    };
+    let mut counts = [0u8; 14];
+    for card in five {
+        counts[value(card) as usize] += 1;
+    }
+    let mut ranks: Vec<u8> = five.iter().map(value).collect();
+    ranks.sort_unstable_by_key(|&rank| std::cmp::Reverse((counts[rank as usize], rank)));
+    let groups: Vec<u8> = ranks
+        .iter()
+        .dedup()
+        .map(|&rank| counts[rank as usize])
+        .collect();
+    let pairing = match groups[..] {
+        [1, 1, 1, 1, 1] => 0,
+        [2, 1, 1, 1] => 1,
+        [2, 2, 1] => 2,
+        [3, 1, 1] => 3,
+        [3, 2] => 4,
+        _ => 5,
+    };
+    AceFiveLow {
+        pairing,
+        ranks: [ranks[0], ranks[1], ranks[2], ranks[3], ranks[4]],
+    }
+}
+
+// A deuce-to-seven low: the five cards ranked as a high hand with the ace always high, so
+// A-5-4-3-2 is ace high rather than a straight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeuceSevenLow(HandValue);
+
+impl DeuceSevenLow {
+    // The hand as a high hand, e.g. HighCard for 7-5-4-3-2 or Straight for 6-5-4-3-2
+    pub fn to_hand_rank(&self) -> HandRank {
+        self.0.to_hand_rank()
+    }
+}
+
+// The best deuce-to-seven low among five or more cards
+pub fn evaluate_low_27(cards: CardSet) -> DeuceSevenLow {
+    cards
+        .iter()
+        .combinations(5)
+        .map(|five| DeuceSevenLow(evaluate_ace_high(five.into_iter().collect())))
+        .min()
+        .unwrap()
+}
+
+// Ranks from the ace as 1 up to the king as 13, e.g. "8-6-4-2-A"
+fn low_ranks_string(ranks: &[u8]) -> String {
+    let chars: Vec<String> = ranks
+        .iter()
+        .map(|&value| match value {
+            1 => 'A'.to_string(),
+            _ => rank_to_char(RANKS[value as usize - 2]).to_string(),
+        })
+        .collect();
+    chars.join("-")
+}
+
+impl fmt::Display for LowHandRank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", low_ranks_string(&self.0))
+    }
+}
+
+// Reads back the Display form, e.g. "8-6-4-2-A"
+impl FromStr for LowHandRank {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<LowHandRank, String> {
+        let invalid = || format!("invalid low: {}", text);
+        let values: Vec<u8> = text
+            .split('-')
+            .map(|rank| match rank.parse::<Rank>() {
+                Ok(Rank::Ace) => Ok(1),
+                Ok(rank) if rank <= Rank::Eight => Ok(rank as u8),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<_, _>>()?;
+        let five: [u8; 5] = values.try_into().map_err(|_| invalid())?;
+        if five.windows(2).any(|pair| pair[0] <= pair[1]) {
+            return Err(invalid());
+        }
+        Ok(LowHandRank(five))
+    }
+}
+
+impl fmt::Display for AceFiveLow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", low_ranks_string(&self.ranks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_cards;
+    use crate::Rank;
+
+    fn cards(text: &str) -> CardSet {
+        CardSet::from(&parse_cards(text).unwrap()[..])
+    }
+
+    fn a5(text: &str) -> AceFiveLow {
+        evaluate_low_a5(cards(text))
+    }
+
+    fn d27(text: &str) -> DeuceSevenLow {
+        evaluate_low_27(cards(text))
+    }
+
+    const WHEEL: &str = "As 2d 3c 4h 5s";
+
+    #[test]
+    fn wheel_is_the_best_a5_low_but_a_2_7_straight() {
+        assert_eq!(a5(WHEEL).to_string(), "5-4-3-2-A");
+        assert!(a5(WHEEL) < a5("6s 4d 3c 2h As"));
+        assert!(a5(WHEEL) < a5("6s 4s 3s 2s As"));
+        assert_eq!(
+            d27("6s 5d 4c 3h 2s").to_hand_rank(),
+            HandRank::Straight(Rank::Six)
+        );
+        assert!(d27("7s 5d 4c 3h 2s") < d27("6s 5d 4c 3h 2s"));
+    }
+
+    #[test]
+    fn ace_plays_high_in_2_7() {
+        assert!(matches!(d27(WHEEL).to_hand_rank(), HandRank::HighCard(_)));
+        assert!(d27("Ks Qd Jc 9h 8s") < d27(WHEEL));
+        // The ace low is the best a-5 card and the worst 2-7 one
+        assert!(a5("As 3d 4c 6h 7s") < a5("Ks 3d 4c 6h 7s"));
+        assert!(d27("Ks 3d 4c 6h 7s") < d27("As 3d 4c 6h 7s"));
+    }
+
+    #[test]
+    fn flushes_count_only_in_2_7() {
+        let suited = "7h 5h 4h 3h 2h";
+        assert!(matches!(d27(suited).to_hand_rank(), HandRank::Flush(_)));
+        assert!(d27("8s 6d 4c 3h 2s") < d27(suited));
+        assert_eq!(a5(suited), a5("7h 5d 4h 3h 2h"));
+    }
+
+    #[test]
+    fn pairs_break_ties_rank_by_rank() {
+        assert!(a5("5s 5d 7c 6h 4s") < a5("5s 5d 8c 3h 2s"));
+        assert!(a5("Ks Qd Jc 9h 8s") < a5("2s 2d 3c 4h 5s"));
+        assert!(a5("2s 2d 3c 4h 5s") < a5("2s 2d 3c 3h 4s"));
+        assert!(d27("5s 5d 7c 6h 4s") < d27("5s 5d 8c 3h 2s"));
+        // The best five of seven
+        assert_eq!(a5("Ks Kd 2c 3h 4s 5d As").to_string(), "5-4-3-2-A");
+        assert_eq!(
+            d27("7s 5d 4c 3h 2s Kd Kc").to_hand_rank(),
+            d27("7s 5d 4c 3h 2s").to_hand_rank()
+        );
+    }
+
+    #[test]
+    fn eight_or_better() {
+        assert_eq!(
+            evaluate_low_eight(cards("As 2d 3c 4h 5s 9d Kc"))
+                .unwrap()
+                .to_string(),
+            "5-4-3-2-A"
+        );
+        assert!(evaluate_low_eight(cards("As 2d 3c 4h 9s 9d Kc")).is_none());
+    }
+}
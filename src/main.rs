@@ -1,326 +1,1414 @@
-use itertools::Itertools;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
-use rayon::prelude::*;
+use clap::{Arg, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use pokersim::aggregate::{downcast, Aggregator};
+#[cfg(feature = "alloc-stats")]
+use pokersim::alloc_counter;
+use pokersim::bitboard::CardSet;
+use pokersim::cache::QueryCache;
+use pokersim::callers::{self, HandClass};
+use pokersim::chunk_log::ChunkLog;
+use pokersim::composition::DeckComposition;
+use pokersim::config::Config;
+use pokersim::constraint::HandConstraint;
+use pokersim::draw::StandardDraw;
+use pokersim::equity::{self, Holding, Scenario};
+use pokersim::filter::{self, HandFilter};
+use pokersim::folding::{position_name, FoldModel, TablePreset};
+use pokersim::hooks::{HandHook, LogHook, SampleHook};
+use pokersim::line::Line;
+use pokersim::progress::Progress;
+use pokersim::query::{Query, QueryCounts, QueryStats};
+use pokersim::range::Range;
+#[cfg(feature = "json")]
+use pokersim::report;
+use pokersim::simulator::{self, SeatResults, SimulationConfig};
+use pokersim::streets::StreetStats;
+use pokersim::transitions::TransitionStats;
+use pokersim::variant::Variant;
+use pokersim::{combinatorics, dataset, notation, promo, rng};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum Suit {
-    Clubs,
-    Diamonds,
-    Hearts,
-    Spades,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum Rank {
-    Two = 2,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King,
-    Ace,
-}
-
-#[derive(Debug, Clone, Copy)]
-struct Card {
-    rank: Rank,
-    suit: Suit,
-}
-
-impl Card {
-    fn new(rank: Rank, suit: Suit) -> Self {
-        Card { rank, suit }
-    }
-}
-
-struct Deck {
-    cards: Vec<Card>,
-}
-
-impl Deck {
-    fn new() -> Self {
-        let mut cards = Vec::with_capacity(52);
-        for &suit in &[Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades] {
-            for rank_value in 2..=14 {
-                let rank = match rank_value {
-                    2 => Rank::Two,
-                    3 => Rank::Three,
-                    4 => Rank::Four,
-                    5 => Rank::Five,
-                    6 => Rank::Six,
-                    7 => Rank::Seven,
-                    8 => Rank::Eight,
-                    9 => Rank::Nine,
-                    10 => Rank::Ten,
-                    11 => Rank::Jack,
-                    12 => Rank::Queen,
-                    13 => Rank::King,
-                    14 => Rank::Ace,
-                    _ => unreachable!(),
-                };
-                cards.push(Card::new(rank, suit));
-            }
-        }
-        Deck { cards }
-    }
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
 
-    fn shuffle(&mut self) {
-        let mut rng = thread_rng();
-        self.cards.shuffle(&mut rng);
-    }
+// The counting allocator is installed by the binary, never imposed on library users
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static GLOBAL: alloc_counter::CountingAllocator = alloc_counter::CountingAllocator;
+
+#[derive(Parser)]
+#[command(
+    name = "pokersim",
+    version,
+    about = "Monte Carlo Texas Hold'em simulator",
+    after_help = "Flag defaults can be set in ~/.config/pokersim/config.toml (`threads = 4`, \
+                  or `trials = 500000` under `[equity]`) and overridden by environment \
+                  variables such as POKERSIM_THREADS or POKERSIM_EQUITY_TRIALS."
+)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    simulate: SimulateArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Normalize an action line such as "r2.5c/x b33 c" and spell it out
+    Line(LineArgs),
+    /// Write reference-labelled hand comparisons as CSV
+    GenDataset(GenDatasetArgs),
+    /// Chance of hitting a draw
+    Outs(OutsArgs),
+    /// Combos of a hand class such as AKs, AKo, AK or QQ left after card removal
+    Combos(CombosArgs),
+    /// What is left in the deck after card removal: suits, ranks and possible hands
+    Deck(DeckArgs),
+    /// Expected value of a high-hand promotion
+    Promo(PromoArgs),
+    /// Equity of starting hands against 1 to 8 callers who all see the showdown
+    Callers(CallersArgs),
+    /// Win, tie and equity of all 169 starting hands against random opponents as a 13x13 grid
+    Grid(GridArgs),
+    /// Win, tie and equity percentages of known hands, e.g. "AsKs vs QdQc"
+    Equity(EquityArgs),
+    /// Print a shell completion script, or write man pages for every command
+    Completions(CompletionsArgs),
+}
+
+/// Run the simulation (the default when no subcommand is given)
+#[derive(Args)]
+struct SimulateArgs {
+    /// Number of games to simulate
+    #[arg(long, default_value_t = 1_000_000)]
+    games: usize,
+    /// Players at the table
+    #[arg(long, default_value_t = 6, value_parser = parse_players)]
+    players: usize,
+    /// Worker threads; defaults to one per core. 1 plays every game in order on the main
+    /// thread without a thread pool, for debugging and profiling, with the same results
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Keep a random sample of hands matching a filter and print them at the end
+    #[arg(long = "sample", value_name = "FILTER", value_parser = HandFilter::parse)]
+    sample_filter: Option<HandFilter>,
+    /// How many hands --sample keeps
+    #[arg(long, default_value_t = 10)]
+    sample_size: usize,
+    /// Print every hand matching a filter as it is played
+    #[arg(long = "log", value_name = "FILTER", value_parser = HandFilter::parse)]
+    log_filter: Option<HandFilter>,
+    /// Estimate a conditional probability such as "P(hero.win | hero.pocket_pair)"
+    #[arg(long = "query", value_parser = Query::parse)]
+    queries: Vec<Query>,
+    /// Neither read nor write the query cache
+    #[arg(long)]
+    no_cache: bool,
+    /// Stop after this many seconds
+    #[arg(long, value_parser = parse_seconds)]
+    max_seconds: Option<f64>,
+    /// Stop after this many games
+    #[arg(long)]
+    max_iterations: Option<usize>,
+    /// Report per-game simulation time percentiles
+    #[arg(long)]
+    timing: bool,
+    /// Print throughput, allocations and latency for every chunk to stderr
+    #[arg(long)]
+    chunk_stats: bool,
+    /// Do not show the progress line, games per second and time left on stderr
+    #[arg(long)]
+    quiet: bool,
+    /// Write the per-chunk statistics as CSV
+    #[arg(long, value_name = "PATH")]
+    chunk_csv: Option<String>,
+    /// Break the time of every game down by phase: shuffle, deal, evaluation,
+    /// showdown, aggregation and hooks
+    #[arg(long)]
+    profile: bool,
+    /// Check every deal for duplicate cards in release builds too
+    #[arg(long)]
+    paranoid: bool,
+    /// Report hand category frequencies on the flop, turn and river (5th to 7th street in stud)
+    #[arg(long)]
+    streets: bool,
+    /// Write how often each hand class on the flop, e.g. a flush draw, becomes each class on
+    /// the turn and river, as CSV
+    #[arg(long, value_name = "PATH")]
+    transitions: Option<String>,
+    /// Fold all but the strongest share of starting hands preflop, e.g. 0.25
+    #[arg(long, value_parser = parse_vpip)]
+    vpip: Option<f64>,
+    /// Fold preflop by position like players at this kind of table: full-ring, 6max or loose.
+    /// Early seats play tight and the button wide
+    #[arg(long, value_name = "PRESET", conflicts_with = "vpip")]
+    table: Option<TablePreset>,
+    /// Game to deal: holdem; omaha for four hole cards of which exactly two play; plo5 and
+    /// plo6 for Omaha with five and six; omaha8 and bigo for Omaha Hi-Lo, eight or better,
+    /// with four and five; courchevel for five-card Omaha with a card of the flop exposed
+    /// preflop; irish for Omaha discarding down to two hole cards on the flop; shortdeck for
+    /// Hold'em without twos through fives; stud for seven cards each and no board; or draw for
+    /// Five Card Draw
+    #[arg(long, default_value_t = Variant::Holdem)]
+    variant: Variant,
+    /// Most cards a player replaces at the draw in draw; 0 stands pat on every hand
+    /// [default: 3]
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=5))]
+    max_draw: Option<u8>,
+    /// Seed for the random number generator, to reproduce a run exactly
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Print the results as text or as one JSON document, or write them as CSV files of
+    /// seat results and hand categories
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    /// Directory --output csv writes seats.csv and categories.csv into
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    output_dir: PathBuf,
+    /// Warn unless every seat's equity is known to within this many percentage points at
+    /// 95% confidence, e.g. 0.1
+    #[arg(long, value_name = "PERCENT", value_parser = parse_precision)]
+    precision: Option<f64>,
+    /// Stop as soon as every seat's equity is within --precision, with --games as the most
+    /// to play
+    #[arg(long, requires = "precision")]
+    converge: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Args)]
+struct LineArgs {
+    notation: String,
+}
+
+#[derive(Args)]
+struct GenDatasetArgs {
+    /// Number of hand comparisons
+    #[arg(long, default_value_t = 100_000)]
+    count: usize,
+    /// Output file; stdout when omitted
+    #[arg(long, value_name = "PATH")]
+    out: Option<String>,
+    /// Seed for the random number generator, to reproduce a run exactly
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Args)]
+struct OutsArgs {
+    outs: u64,
+    /// Cards the outs are drawn from
+    #[arg(long, default_value_t = 47)]
+    unseen: u64,
+    /// Cards already seen, e.g. "Ah Kd 7c 8c 9s"; the rest of the deck is unseen
+    #[arg(long, value_name = "CARDS", conflicts_with = "unseen")]
+    seen: Option<String>,
+    /// Cards still to be dealt
+    #[arg(long, default_value_t = 2)]
+    to_come: u64,
+}
+
+#[derive(Args)]
+struct CombosArgs {
+    class: String,
+    /// Cards known to be out of the deck, e.g. "Ah Kd 7c"
+    #[arg(long, value_name = "CARDS")]
+    dead: Option<String>,
+}
+
+#[derive(Args)]
+struct DeckArgs {
+    /// Cards known to be out of the deck, e.g. "Ah Kd 7c"
+    cards: Option<String>,
+}
+
+#[derive(Args)]
+struct PromoArgs {
+    #[arg(long, default_value_t = 10)]
+    tables: usize,
+    /// Players per table
+    #[arg(long, default_value_t = 6, value_parser = parse_players)]
+    players: usize,
+    #[arg(long, default_value_t = 30)]
+    hands_per_hour: usize,
+    /// Length of one promotion window
+    #[arg(long, default_value_t = 1)]
+    window_hours: usize,
+    #[arg(long, default_value_t = 1000)]
+    windows: usize,
+    /// Lowest hand category that qualifies
+    #[arg(long, default_value = "FourOfAKind", value_parser = parse_category)]
+    qualifier: i64,
+    /// Prize paid per window
+    #[arg(long, default_value_t = 500.0)]
+    prize: f64,
+    /// Seed for the random number generator, to reproduce a run exactly
+    #[arg(long)]
+    seed: Option<u64>,
+}
 
-    fn deal(&mut self) -> Option<Card> {
-        self.cards.pop()
+#[derive(Args)]
+struct CallersArgs {
+    /// Hand classes such as AJo, T9s or 77; all 169 when omitted
+    #[arg(value_parser = HandClass::parse)]
+    hands: Vec<HandClass>,
+    /// Deals per hand and number of callers
+    #[arg(long, default_value_t = 2000)]
+    trials: usize,
+    /// Largest number of callers
+    #[arg(long, default_value_t = 8, value_parser = parse_callers)]
+    max_callers: usize,
+    /// Write the table as CSV instead of printing it
+    #[arg(long, value_name = "PATH")]
+    csv: Option<String>,
+    /// Also draw each hand's equity curve as a bar chart
+    #[arg(long)]
+    chart: bool,
+    /// Seed for the random number generator, to reproduce a run exactly
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Args)]
+struct GridArgs {
+    /// Opponents with random hands who all see the showdown
+    #[arg(long, default_value_t = 1, value_parser = parse_callers)]
+    opponents: usize,
+    /// Deals per hand
+    #[arg(long, default_value_t = 10_000)]
+    trials: usize,
+    /// Write win, tie and equity of every hand as CSV instead of printing the grids
+    #[arg(long, value_name = "PATH")]
+    csv: Option<String>,
+    /// Seed for the random number generator, to reproduce a run exactly
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Args)]
+struct EquityArgs {
+    /// Hole cards of each known hand, e.g. AsKs QdQc, or a range of hands such as
+    /// "QQ+,AKs" or "22-55,A2s-A5s,KQ"; a "vs" between hands is ignored
+    #[arg(required = true)]
+    hands: Vec<String>,
+    /// Known board cards, e.g. "Ah 7c 2d"
+    #[arg(long, value_name = "CARDS")]
+    board: Option<String>,
+    /// Players with random hands in addition to the known ones
+    #[arg(long, default_value_t = 0)]
+    random: usize,
+    /// Add an opponent whose unknown hand meets a constraint, e.g. "has A", "pair" or
+    /// "suited, no s"; may be repeated
+    #[arg(long = "villain", value_name = "CONSTRAINT", value_parser = HandConstraint::parse)]
+    villains: Vec<HandConstraint>,
+    /// Random deals of the unknown cards
+    #[arg(long, default_value_t = 100_000, value_parser = parse_trials)]
+    trials: usize,
+    /// Enumerate every runout of the board instead of sampling
+    #[arg(long, conflicts_with = "trials")]
+    exact: bool,
+    /// Seed for the random number generator, to reproduce a run exactly
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Print the results as text, as one JSON document or as CSV
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    /// Warn unless every hand's equity is known to within this many percentage points at
+    /// 95% confidence, e.g. 0.1
+    #[arg(long, value_name = "PERCENT", value_parser = parse_precision, conflicts_with = "exact")]
+    precision: Option<f64>,
+}
+
+#[derive(Args)]
+struct CompletionsArgs {
+    /// Shell to complete for, e.g. bash, zsh or fish
+    #[arg(required_unless_present = "man_dir")]
+    shell: Option<clap_complete::Shell>,
+    /// Write pokersim.1 and one page per subcommand into this directory instead
+    #[arg(long, value_name = "DIR", conflicts_with = "shell")]
+    man_dir: Option<String>,
+}
+
+fn parse_players(text: &str) -> Result<usize, String> {
+    match text.parse() {
+        Ok(players) if (2..=23).contains(&players) => Ok(players),
+        _ => Err("expected 2-23 players".to_string()),
     }
 }
 
-#[derive(Debug)]
-struct Player {
-    hand: Vec<Card>,
+fn parse_callers(text: &str) -> Result<usize, String> {
+    match text.parse() {
+        Ok(callers) if (1..=22).contains(&callers) => Ok(callers),
+        _ => Err("expected 1-22 callers".to_string()),
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum HandRank {
-    HighCard(Rank),
-    OnePair(Rank),
-    TwoPair(Rank, Rank),
-    ThreeOfAKind(Rank),
-    Straight(Rank),
-    Flush(Rank),
-    FullHouse(Rank, Rank),
-    FourOfAKind(Rank),
-    StraightFlush(Rank),
-    RoyalFlush,
+fn parse_trials(text: &str) -> Result<usize, String> {
+    match text.parse() {
+        Ok(trials) if trials >= 1 => Ok(trials),
+        _ => Err("expected at least 1 trial".to_string()),
+    }
 }
 
-// Function to map HandRank instances to their categories
-fn hand_rank_category(hand_rank: &HandRank) -> &'static str {
-    match hand_rank {
-        HandRank::HighCard(_) => "HighCard",
-        HandRank::OnePair(_) => "OnePair",
-        HandRank::TwoPair(_, _) => "TwoPair",
-        HandRank::ThreeOfAKind(_) => "ThreeOfAKind",
-        HandRank::Straight(_) => "Straight",
-        HandRank::Flush(_) => "Flush",
-        HandRank::FullHouse(_, _) => "FullHouse",
-        HandRank::FourOfAKind(_) => "FourOfAKind",
-        HandRank::StraightFlush(_) => "StraightFlush",
-        HandRank::RoyalFlush => "RoyalFlush",
+fn parse_vpip(text: &str) -> Result<f64, String> {
+    match text.parse() {
+        Ok(vpip) if vpip > 0.0 && vpip <= 1.0 => Ok(vpip),
+        _ => Err("expected a share of hands in (0, 1]".to_string()),
     }
 }
 
-fn is_sequence(mut ranks: Vec<u8>) -> bool {
-    ranks.sort_unstable();
-    ranks.dedup();
+fn parse_precision(text: &str) -> Result<f64, String> {
+    match text.parse() {
+        Ok(precision) if precision > 0.0 && precision < 100.0 => Ok(precision),
+        _ => Err("expected a number of percentage points in (0, 100)".to_string()),
+    }
+}
 
-    if ranks.len() != 5 {
-        return false;
+fn parse_seconds(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(seconds),
+        _ => Err("expected a number of seconds, at least 0".to_string()),
     }
+}
 
-    let is_regular_straight = ranks[4] - ranks[0] == 4;
+fn parse_category(text: &str) -> Result<i64, String> {
+    filter::CATEGORIES
+        .iter()
+        .position(|&name| name == text)
+        .map(|value| value as i64)
+        .ok_or_else(|| format!("unknown category: {}", text))
+}
 
-    // Special case for wheel straight (A-2-3-4-5)
-    let is_wheel_straight = ranks == vec![2, 3, 4, 5, 14];
+// The given seed, or a fresh one from OS entropy
+fn resolve_seed(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(rng::random_seed)
+}
 
-    is_regular_straight || is_wheel_straight
+// JSON is written through serde, which builds can leave out
+fn check_output(output: OutputFormat) -> Result<(), String> {
+    if output == OutputFormat::Json && !cfg!(feature = "json") {
+        return Err("built without the json feature, so only text output is available".to_string());
+    }
+    Ok(())
 }
 
-fn get_rank_counts(ranks: &[Rank]) -> HashMap<Rank, u8> {
-    let mut counts = HashMap::new();
-    for &rank in ranks {
-        *counts.entry(rank).or_insert(0) += 1;
+// Warn on stderr when the equities of `games` games are less precise than `precision`
+// percentage points, with about how many games would reach it
+fn warn_precision(precision: Option<f64>, seats: &[SeatResults], games: usize) {
+    let Some(precision) = precision else {
+        return;
+    };
+    let margin = simulator::worst_equity_margin(seats, games) * 100.0;
+    if margin > precision {
+        let needed = games as f64 * (margin / precision).powi(2);
+        eprintln!(
+            "warning: equities are only known to within \u{b1}{:.3}% at 95% confidence, short of \
+             the requested \u{b1}{}%; about {:.0} games would reach it",
+            margin, precision, needed
+        );
     }
-    counts
 }
 
-fn evaluate_five_card_hand(cards: &[&Card]) -> HandRank {
-    let mut ranks: Vec<Rank> = cards.iter().map(|c| c.rank).collect();
-    let mut rank_values: Vec<u8> = ranks.iter().map(|&r| r as u8).collect();
-    ranks.sort_by(|a, b| b.cmp(a)); // Sort descending
-    rank_values.sort_unstable();
-    rank_values.dedup();
+// A CSV field, quoted when it holds a comma, e.g. a range such as "QQ+,AKs"
+fn csv_field(text: &str) -> String {
+    if text.contains(',') || text.contains('"') {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
 
-    let suits: Vec<Suit> = cards.iter().map(|c| c.suit).collect();
+// One row per seat with its wins, ties and equity over `games` games, as counts and
+// percentages with standard errors and the equity's 95% confidence interval in
+// percentage points
+fn write_seats_csv(
+    out: &mut impl Write,
+    label: &str,
+    seats: &[(String, SeatResults)],
+    games: usize,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{},games,wins,win_percent,win_se,ties,tie_percent,tie_se,pots_won,equity_percent,equity_se,\
+         equity_low,equity_high",
+        label
+    )?;
+    for (name, results) in seats {
+        let share = |count: f64| count / games.max(1) as f64;
+        let (win, tie) = (share(results.wins as f64), share(results.ties as f64));
+        let (equity, low, high) = results.equity_interval(games);
+        writeln!(
+            out,
+            "{},{},{},{:.4},{:.4},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4}",
+            csv_field(name),
+            games,
+            results.wins,
+            win * 100.0,
+            simulator::standard_error(win, games) * 100.0,
+            results.ties,
+            tie * 100.0,
+            simulator::standard_error(tie, games) * 100.0,
+            results.equity(),
+            equity * 100.0,
+            results.equity_standard_error(games) * 100.0,
+            low * 100.0,
+            high * 100.0
+        )?;
+    }
+    out.flush()
+}
 
-    let is_flush = suits.iter().all(|&s| s == suits[0]);
+// One row per hand category with how many of `hands` showdown hands it made up
+fn write_categories_csv(
+    out: &mut impl Write,
+    counts: &HashMap<&'static str, usize>,
+    hands: usize,
+) -> io::Result<()> {
+    writeln!(out, "category,hands,percent,se")?;
+    for category in filter::CATEGORIES {
+        let count = counts.get(category).copied().unwrap_or(0);
+        let share = count as f64 / hands.max(1) as f64;
+        writeln!(
+            out,
+            "{},{},{:.4},{:.4}",
+            category,
+            count,
+            share * 100.0,
+            simulator::standard_error(share, hands) * 100.0
+        )?;
+    }
+    out.flush()
+}
 
-    let is_straight = is_sequence(rank_values.clone());
+// Every sampled result starts with the seed that reproduces it, given or generated
+fn seed_line(seed: u64) -> String {
+    format!(
+        "Seed: {} (pass --seed {} to reproduce this run)",
+        seed, seed
+    )
+}
 
-    if is_flush && is_straight {
-        if ranks.contains(&Rank::Ace) && ranks.contains(&Rank::King) {
-            return HandRank::RoyalFlush;
-        } else {
-            return HandRank::StraightFlush(ranks[0]);
+fn print_query_results(queries: &[Query], query_counts: &[QueryCounts]) {
+    println!("\nQueries:");
+    for (query, counts) in queries.iter().zip(query_counts) {
+        match (counts.probability(), counts.standard_error()) {
+            (Some(probability), Some(error)) => println!(
+                "{} = {:.4} (standard error {:.4}, {} conditioning hands)",
+                query.describe(),
+                probability,
+                error,
+                counts.conditioned
+            ),
+            _ => println!("{} = n/a (no conditioning hands)", query.describe()),
         }
     }
+}
 
-    let rank_counts = get_rank_counts(&ranks);
+// `pokersim line <notation>`: normalize an action line and spell it out
+fn run_line(args: LineArgs) -> Result<(), String> {
+    let line: Line = args.notation.parse()?;
+    println!("{}", line);
+    print!("{}", line.describe());
+    Ok(())
+}
 
-    let counts: Vec<u8> = rank_counts.values().cloned().collect();
-    if counts.contains(&4) {
-        let rank = *rank_counts
-            .iter()
-            .find(|&(_, &count)| count == 4)
-            .unwrap()
-            .0;
-        return HandRank::FourOfAKind(rank);
-    }
+// `pokersim gen-dataset [--count N] [--out PATH]`: reference-labelled hand comparisons
+fn run_gen_dataset(args: GenDatasetArgs) -> Result<(), String> {
+    let seed = resolve_seed(args.seed);
+    // stdout may be the CSV itself
+    eprintln!("{}", seed_line(seed));
+    let result = match args.out {
+        Some(path) => {
+            let file = File::create(&path).map_err(|err| format!("{}: {}", path, err))?;
+            dataset::write_dataset(BufWriter::new(file), args.count, seed)
+        }
+        None => dataset::write_dataset(BufWriter::new(io::stdout().lock()), args.count, seed),
+    };
+    result.map_err(|err| err.to_string())
+}
 
-    if counts.contains(&3) && counts.contains(&2) {
-        let three_rank = *rank_counts
-            .iter()
-            .find(|&(_, &count)| count == 3)
-            .unwrap()
-            .0;
-        let two_rank = *rank_counts
-            .iter()
-            .find(|&(_, &count)| count == 2)
-            .unwrap()
-            .0;
-        return HandRank::FullHouse(three_rank, two_rank);
+// `pokersim outs <outs> [--unseen N] [--to-come N]`: chance of hitting a draw
+fn run_outs(args: OutsArgs) -> Result<(), String> {
+    let OutsArgs {
+        outs,
+        mut unseen,
+        seen,
+        to_come,
+    } = args;
+    if let Some(seen) = seen {
+        let seen: CardSet = notation::parse_cards(&seen)?.into_iter().collect();
+        unseen = DeckComposition::new(seen).len() as u64;
+    }
+    if outs > unseen || to_come > unseen {
+        return Err("outs and cards to come cannot exceed the unseen cards".to_string());
     }
 
-    if is_flush {
-        return HandRank::Flush(ranks[0]);
+    let hit = combinatorics::prob_at_least_one(outs, unseen, to_come);
+    println!(
+        "{} outs, {} unseen cards, {} to come",
+        outs, unseen, to_come
+    );
+    println!(
+        "Hit at least once: {:.2}% ({:.2} to 1 against)",
+        hit * 100.0,
+        combinatorics::odds_against(hit)
+    );
+    for hits in 1..=to_come.min(outs) {
+        let exactly = combinatorics::hypergeometric(unseen, outs, to_come, hits);
+        println!("Exactly {}: {:.2}%", hits, exactly * 100.0);
     }
+    Ok(())
+}
 
-    if is_straight {
-        return HandRank::Straight(ranks[0]);
+// `pokersim combos <class> [--dead CARDS]`: combos of e.g. AKs, AKo, AK or QQ left
+// after card removal
+fn run_combos(args: CombosArgs) -> Result<(), String> {
+    let class = args.class.as_str();
+    let dead = match &args.dead {
+        Some(cards) => notation::parse_cards(cards)?,
+        None => Vec::new(),
+    };
+
+    let (high, low, suited) = callers::parse_class(class)?;
+
+    let remaining = combinatorics::hand_class_combos(high, low, suited, &dead);
+    let total = combinatorics::hand_class_combos(high, low, suited, &[]);
+    println!("{}: {} of {} combos remain", class, remaining, total);
+    Ok(())
+}
+
+// `pokersim deck [CARDS]`: the composition of the deck left after removing `CARDS`
+fn run_deck(args: DeckArgs) -> Result<(), String> {
+    let seen: CardSet = match &args.cards {
+        Some(cards) => notation::parse_cards(cards)?.into_iter().collect(),
+        None => CardSet::new(),
+    };
+    let deck = DeckComposition::new(seen);
+    println!("{} cards left", deck.len());
+    print!("Suits:");
+    for suit in notation::SUITS {
+        print!(
+            " {} {}",
+            notation::suit_to_char(suit),
+            deck.suit_count(suit)
+        );
+    }
+    println!();
+    print!("Ranks:");
+    for rank in notation::RANKS.iter().rev() {
+        print!(
+            " {} {}",
+            notation::rank_to_char(*rank),
+            deck.rank_count(*rank)
+        );
     }
+    println!();
+    println!(
+        "Starting hands: {} combos, {} suited, {} pocket pairs",
+        deck.hand_combos(),
+        deck.suited_combos(),
+        deck.all_pair_combos()
+    );
+    Ok(())
+}
 
-    if counts.contains(&3) {
-        let rank = *rank_counts
-            .iter()
-            .find(|&(_, &count)| count == 3)
-            .unwrap()
-            .0;
-        return HandRank::ThreeOfAKind(rank);
+// `pokersim promo [...]`: expected value of a high-hand promotion
+fn run_promo(args: PromoArgs) -> Result<(), String> {
+    let settings = promo::PromoSettings {
+        tables: args.tables,
+        players_per_table: args.players,
+        hands_per_hour: args.hands_per_hour,
+        window_hours: args.window_hours,
+        windows: args.windows,
+        qualifier: args.qualifier,
+        prize: args.prize,
+    };
+    if settings.total_hands() == 0 {
+        return Err("promotion needs at least one hand".to_string());
     }
 
-    let pair_ranks: Vec<Rank> = rank_counts
-        .iter()
-        .filter(|&(_, &count)| count == 2)
-        .map(|(&rank, _)| rank)
+    let num_players = settings.players_per_table;
+    let total_hands = settings.total_hands();
+    let mut hook = promo::HighHandHook::new(settings);
+    let config = SimulationConfig {
+        seed: resolve_seed(args.seed),
+        ..SimulationConfig::new(total_hands, num_players)
+    };
+    println!("{}", seed_line(config.seed));
+    simulator::run_simulation(&config, &[&hook], Vec::new(), |_| {});
+    hook.finish();
+    Ok(())
+}
+
+// `pokersim callers [HANDS...]`: how equity falls as more players see the flop
+fn run_callers(args: CallersArgs) -> Result<(), String> {
+    let hands = if args.hands.is_empty() {
+        HandClass::all()
+    } else {
+        args.hands
+    };
+    let callers = 1..=args.max_callers;
+    let seed = resolve_seed(args.seed);
+    println!("{}", seed_line(seed));
+    let rows: Vec<(HandClass, Vec<f64>)> = hands
+        .into_iter()
+        .map(|hand| {
+            let equities = callers
+                .clone()
+                .map(|n| callers::equity_vs_callers(hand, n, args.trials, seed))
+                .collect();
+            (hand, equities)
+        })
         .collect();
 
-    if pair_ranks.len() == 2 {
-        return HandRank::TwoPair(pair_ranks[0], pair_ranks[1]);
-    } else if pair_ranks.len() == 1 {
-        return HandRank::OnePair(pair_ranks[0]);
+    match &args.csv {
+        Some(path) => {
+            let write = || -> io::Result<()> {
+                let mut out = BufWriter::new(File::create(path)?);
+                let header: Vec<String> = callers.clone().map(|n| n.to_string()).collect();
+                writeln!(out, "hand,{}", header.join(","))?;
+                for (hand, equities) in &rows {
+                    let fields: Vec<String> = equities
+                        .iter()
+                        .map(|equity| format!("{:.4}", equity))
+                        .collect();
+                    writeln!(out, "{},{}", hand, fields.join(","))?;
+                }
+                out.flush()
+            };
+            write().map_err(|err| format!("{}: {}", path, err))?;
+        }
+        None => {
+            print!("Hand");
+            for n in callers.clone() {
+                print!("{:>7}", n);
+            }
+            println!();
+            for (hand, equities) in &rows {
+                print!("{:<4}", hand.to_string());
+                for equity in equities {
+                    print!("{:>6.1}%", equity * 100.0);
+                }
+                println!();
+            }
+        }
     }
 
-    HandRank::HighCard(ranks[0])
+    if args.chart {
+        for (hand, equities) in &rows {
+            println!("\n{} equity by number of callers:", hand);
+            for (n, equity) in callers.clone().zip(equities) {
+                let bar = "#".repeat((equity * 50.0).round() as usize);
+                println!("{:>2} {:>6.2}% {}", n, equity * 100.0, bar);
+            }
+        }
+    }
+    Ok(())
 }
 
-fn evaluate_hand(cards: &[Card]) -> HandRank {
-    let mut best_rank = HandRank::HighCard(Rank::Two); // Lowest possible hand
-    for combo in cards.iter().combinations(5) {
-        let rank = evaluate_five_card_hand(&combo);
-        if rank > best_rank {
-            best_rank = rank;
+// `pokersim grid [--opponents N] [--trials N] [--csv PATH]`: every starting hand against
+// random opponents, laid out like a range chart
+fn run_grid(args: GridArgs) -> Result<(), String> {
+    let seed = resolve_seed(args.seed);
+    println!("{}", seed_line(seed));
+    let results: Vec<(HandClass, SeatResults)> = HandClass::all()
+        .into_iter()
+        .map(|hand| {
+            let results = callers::results_vs_callers(hand, args.opponents, args.trials, seed);
+            (hand, results)
+        })
+        .collect();
+    let share = |count: f64| count / args.trials.max(1) as f64;
+
+    if let Some(path) = &args.csv {
+        let write = || -> io::Result<()> {
+            let mut out = BufWriter::new(File::create(path)?);
+            writeln!(out, "hand,win,tie,equity")?;
+            for (hand, results) in &results {
+                writeln!(
+                    out,
+                    "{},{:.4},{:.4},{:.4}",
+                    hand,
+                    share(results.wins as f64),
+                    share(results.ties as f64),
+                    share(results.equity())
+                )?;
+            }
+            out.flush()
+        };
+        return write().map_err(|err| format!("{}: {}", path, err));
+    }
+
+    let ranks: Vec<char> = notation::RANKS
+        .iter()
+        .rev()
+        .map(|&rank| notation::rank_to_char(rank))
+        .collect();
+    for (stat, name) in ["Win", "Tie", "Equity"].iter().enumerate() {
+        println!(
+            "\n{} % against {} opponent{} (suited above the diagonal, offsuit below):",
+            name,
+            args.opponents,
+            if args.opponents == 1 { "" } else { "s" }
+        );
+        print!("  ");
+        for rank in &ranks {
+            print!("{:>6}", rank);
+        }
+        println!();
+        for (row, rank) in ranks.iter().enumerate() {
+            print!("{:<2}", rank);
+            for column in 0..ranks.len() {
+                let hand = HandClass::in_grid(row, column);
+                let (_, results) = results.iter().find(|(class, _)| *class == hand).unwrap();
+                let counts = [results.wins as f64, results.ties as f64, results.equity()];
+                print!("{:>6.1}", share(counts[stat]) * 100.0);
+            }
+            println!();
         }
     }
-    best_rank
+    Ok(())
 }
 
-fn simulate_game(num_players: usize, hand_rank_counts: &mut HashMap<&'static str, usize>) -> usize {
-    let mut deck = Deck::new();
-    deck.shuffle();
-
-    // Deal two hole cards to each player
-    let mut players: Vec<Player> = (0..num_players)
-        .map(|_| Player {
-            hand: vec![deck.deal().unwrap(), deck.deal().unwrap()],
+// `pokersim equity <hands or ranges...> [--board CARDS] [--random N] [--villain C]
+// [--exact]`: odds of known hands and ranges
+fn run_equity(args: EquityArgs) -> Result<(), String> {
+    // Hole cards when they parse as such, otherwise a range
+    let mut seats = args
+        .hands
+        .iter()
+        .filter(|hand| !hand.eq_ignore_ascii_case("vs"))
+        .map(|hand| match notation::parse_cards(hand) {
+            Ok(cards) => Ok(Holding::Known(cards)),
+            Err(_) => Range::parse(hand).map(Holding::Range),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    seats.extend(args.villains.into_iter().map(Holding::Constrained));
+    seats.extend((0..args.random).map(|_| Holding::Random));
+    let board = match &args.board {
+        Some(cards) => notation::parse_cards(cards)?,
+        None => Vec::new(),
+    };
+    let scenario = Scenario::new(seats, board)?;
+    check_output(args.output)?;
+    let json = args.output == OutputFormat::Json;
+    let seed = (!args.exact).then(|| resolve_seed(args.seed));
+    let (results, deals) = match seed {
+        None => equity::exact_equity_by_street(&scenario)?,
+        Some(seed) => {
+            match args.output {
+                OutputFormat::Text => println!("{}", seed_line(seed)),
+                // stdout is the CSV itself
+                OutputFormat::Csv => eprintln!("{}", seed_line(seed)),
+                OutputFormat::Json => {}
+            }
+            (
+                equity::sample_equity(&scenario, args.trials, seed),
+                args.trials,
+            )
+        }
+    };
+    let mut random_players = 0;
+    let names: Vec<String> = scenario
+        .seats
+        .iter()
+        .map(|holding| match holding {
+            Holding::Random => {
+                random_players += 1;
+                format!("{} {}", holding, random_players)
+            }
+            _ => holding.to_string(),
         })
         .collect();
+    warn_precision(args.precision, &results, deals);
+    if json {
+        #[cfg(feature = "json")]
+        {
+            let seats: Vec<(String, _)> = names.into_iter().zip(results).collect();
+            let board = notation::cards_to_string(&scenario.board);
+            println!("{}", report::equity_json(seed, deals, &board, &seats));
+        }
+        return Ok(());
+    }
+    if args.output == OutputFormat::Csv {
+        let seats: Vec<(String, _)> = names.into_iter().zip(results).collect();
+        return write_seats_csv(&mut io::stdout().lock(), "hand", &seats, deals)
+            .map_err(|err| err.to_string());
+    }
 
-    // Shuffle the players to randomize their order
-    players.shuffle(&mut thread_rng());
-
-    // Deal five community cards
-    let mut community_cards = Vec::with_capacity(5);
-    for _ in 0..5 {
-        community_cards.push(deck.deal().unwrap());
+    if !scenario.board.is_empty() {
+        println!("Board: {}", notation::cards_to_string(&scenario.board));
     }
+    if args.exact {
+        let dealt = if scenario.all_known() {
+            "runouts"
+        } else {
+            "deals"
+        };
+        println!("Exact over all {} {}", deals, dealt);
+    }
+    let width = names.iter().map(String::len).max().unwrap_or(0).max(10);
+    print!(
+        "{:<width$} {:>9} {:>9} {:>9}",
+        "Hand", "Win", "Tie", "Equity"
+    );
+    // Sampled equities also show their standard error and 95% confidence interval
+    if seed.is_some() {
+        print!(" {:>8} {:>19}", "\u{b1} SE", "95% CI");
+    }
+    println!();
+    let percent = |count: f64| count / deals.max(1) as f64 * 100.0;
+    for (name, seat_results) in names.iter().zip(&results) {
+        print!(
+            "{:<width$} {:>8.3}% {:>8.3}% {:>8.3}%",
+            name,
+            percent(seat_results.wins as f64),
+            percent(seat_results.ties as f64),
+            percent(seat_results.equity())
+        );
+        if seed.is_some() {
+            let (_, low, high) = seat_results.equity_interval(deals);
+            let interval = format!("{:.3}%-{:.3}%", low * 100.0, high * 100.0);
+            print!(
+                " {:>8.3} {:>19}",
+                seat_results.equity_standard_error(deals) * 100.0,
+                interval
+            );
+        }
+        println!();
+    }
+    Ok(())
+}
 
-    // Evaluate each player's best hand
-    let mut best_hand_rank = HandRank::HighCard(Rank::Two);
-    let mut winner_indices = vec![];
+// The variable that overrides a flag's default, e.g. POKERSIM_THREADS for --threads or
+// POKERSIM_EQUITY_TRIALS for --trials of `pokersim equity`
+fn env_var_name(subcommand: &str, long: &str) -> String {
+    let name = if subcommand.is_empty() {
+        format!("POKERSIM_{}", long)
+    } else {
+        format!("POKERSIM_{}_{}", subcommand, long)
+    };
+    name.to_ascii_uppercase().replace('-', "_")
+}
 
-    for (i, player) in players.iter().enumerate() {
-        let mut all_cards = player.hand.clone();
-        all_cards.extend_from_slice(&community_cards);
-        let hand_rank = evaluate_hand(&all_cards);
+// Replace the defaults of `command`'s flags with config file settings, then with
+// environment variables, so the command line overrides the environment, the environment
+// overrides the file and the file overrides the built-in defaults. Every value is checked
+// here, so a bad one is blamed on the file line or variable it came from rather than on
+// the command line.
+fn with_settings(
+    command: clap::Command,
+    subcommand: &str,
+    config: &Config,
+) -> Result<clap::Command, String> {
+    let mut defaults: Vec<(String, String, String)> = config
+        .settings()
+        .filter(|(section, _, _)| *section == subcommand)
+        .map(|(_, key, value)| {
+            let source = config.location(subcommand, key);
+            (key.to_string(), value.to_string(), source)
+        })
+        .collect();
+    for arg in command.get_arguments() {
+        if let Some(long) = arg.get_long() {
+            let name = env_var_name(subcommand, long);
+            if let Ok(value) = env::var(&name) {
+                defaults.push((long.to_string(), value, name));
+            }
+        }
+    }
 
-        // Get the category of the hand rank
-        let category = hand_rank_category(&hand_rank);
+    defaults
+        .into_iter()
+        .try_fold(command, |command, (long, value, source)| {
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(long.as_str()));
+            let Some(arg) = arg else {
+                return Ok(command);
+            };
+            check_value(arg, &value).map_err(|err| {
+                format!(
+                    "{}: invalid value '{}' for {}: {}",
+                    source, value, long, err
+                )
+            })?;
+            let id = arg.get_id().clone();
+            Ok(command.mut_arg(id, |arg: Arg| arg.default_value(value)))
+        })
+}
 
-        // Count the hand rank category locally
-        *hand_rank_counts.entry(category).or_insert(0) += 1;
+// Run a setting through its flag's own parser, with the reason it was refused; clap
+// parses a default value like a typed one once it is used
+fn check_value(arg: &Arg, value: &str) -> Result<(), String> {
+    let check = Arg::new(arg.get_id().clone())
+        .long(arg.get_long().unwrap().to_string())
+        .action(arg.get_action().clone())
+        .value_parser(arg.get_value_parser().clone())
+        .default_value(value.to_string());
+    let parsed = clap::Command::new("check")
+        .arg(check)
+        .try_get_matches_from(["check"]);
+    let Err(err) = parsed else {
+        return Ok(());
+    };
+    if let Some(reason) = err.source() {
+        return Err(reason.to_string());
+    }
+    let names: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .map(|value| value.get_name().to_string())
+        .collect();
+    Err(match names.is_empty() {
+        true => err.kind().to_string(),
+        false => format!("expected one of {}", names.join(", ")),
+    })
+}
 
-        if hand_rank > best_hand_rank {
-            best_hand_rank = hand_rank;
-            winner_indices.clear();
-            winner_indices.push(i);
-        } else if hand_rank == best_hand_rank {
-            winner_indices.push(i);
+// Settings naming a subcommand or flag that does not exist are mistakes worth reporting
+fn check_settings(command: &clap::Command, config: &Config) -> Result<(), String> {
+    for (section, key, _) in config.settings() {
+        let target = if section.is_empty() {
+            Some(command)
+        } else {
+            command.find_subcommand(section)
+        };
+        let known = target.is_some_and(|target| {
+            target
+                .get_arguments()
+                .any(|arg| arg.get_long() == Some(key))
+        });
+        if !known {
+            let path = config
+                .path()
+                .map_or(String::new(), |path| path.display().to_string());
+            return Err(match section {
+                "" => format!("{}: unknown setting {}", path, key),
+                _ => format!("{}: unknown setting {} in [{}]", path, key, section),
+            });
         }
     }
+    Ok(())
+}
+
+fn parse_cli() -> Result<Cli, String> {
+    let config = Config::load()?;
+    let mut command = Cli::command();
+    check_settings(&command, &config)?;
+    command = with_settings(command, "", &config)?;
+    let subcommands: Vec<clap::Command> = command.get_subcommands().cloned().collect();
+    for subcommand in subcommands {
+        let name = subcommand.get_name().to_string();
+        let subcommand = with_settings(subcommand, &name, &config)?;
+        command = command.mut_subcommand(&name, |_| subcommand);
+    }
+    let matches = command.get_matches();
+    Ok(Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit()))
+}
 
-    // Randomly select a winner among tied players
-    let mut rng = thread_rng();
-    let winner = *winner_indices.choose(&mut rng).unwrap();
+// `pokersim completions <SHELL>` or `pokersim completions --man-dir DIR`
+fn run_completions(args: CompletionsArgs) -> Result<(), String> {
+    let mut command = Cli::command();
+    if let Some(shell) = args.shell {
+        clap_complete::generate(shell, &mut command, "pokersim", &mut io::stdout());
+        return Ok(());
+    }
 
-    winner
+    let dir = PathBuf::from(args.man_dir.unwrap());
+    fs::create_dir_all(&dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+    // Subcommand pages are named like pokersim-equity.1 and list their full usage
+    command.build();
+    let mut pages = vec![("pokersim".to_string(), command.clone())];
+    for subcommand in command
+        .get_subcommands()
+        .filter(|sub| sub.get_name() != "help")
+    {
+        let name = format!("pokersim-{}", subcommand.get_name());
+        pages.push((name.clone(), subcommand.clone().name(name)));
+    }
+    for (name, page) in pages {
+        let path = dir.join(format!("{}.1", name));
+        let mut out = Vec::new();
+        clap_mangen::Man::new(page)
+            .render(&mut out)
+            .and_then(|_| fs::write(&path, out))
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+    }
+    Ok(())
 }
 
 fn main() {
-    let num_games = 1_000_000;
-    let num_players = 6;
+    let cli = parse_cli().unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(2);
+    });
+    let result = match cli.command {
+        Some(Command::Line(args)) => run_line(args),
+        Some(Command::GenDataset(args)) => run_gen_dataset(args),
+        Some(Command::Outs(args)) => run_outs(args),
+        Some(Command::Combos(args)) => run_combos(args),
+        Some(Command::Deck(args)) => run_deck(args),
+        Some(Command::Promo(args)) => run_promo(args),
+        Some(Command::Callers(args)) => run_callers(args),
+        Some(Command::Grid(args)) => run_grid(args),
+        Some(Command::Equity(args)) => run_equity(args),
+        Some(Command::Completions(args)) => run_completions(args),
+        None => run_simulate(cli.simulate),
+    };
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(2);
+    }
+}
 
-    // Use Arc and Mutex for shared data
-    let wins = Arc::new(Mutex::new(vec![0usize; num_players]));
-    let hand_rank_counts = Arc::new(Mutex::new(HashMap::new()));
+// Let the hooks and aggregators report, e.g. dump the sampled hands
+fn finish_reports(hooks: &mut [Box<dyn HandHook>], aggregators: &[Box<dyn Aggregator>]) {
+    for hook in hooks {
+        hook.finish();
+    }
+    for aggregator in aggregators {
+        aggregator.report();
+    }
+}
 
-    (0..num_games).into_par_iter().for_each(|_| {
-        let mut local_hand_rank_counts: HashMap<&'static str, usize> = HashMap::new();
+// `pokersim [options]`: simulate random deals and report per-seat results
+fn run_simulate(options: SimulateArgs) -> Result<(), String> {
+    if let Some(threads) = options.threads {
+        simulator::set_threads(threads)?;
+    }
+    let num_games = options.games;
+    let num_players = options.players;
+    let variant = options.variant;
+    let max_draw = options
+        .max_draw
+        .map_or(StandardDraw::default().max_draw, usize::from);
+    if num_players > variant.max_players(max_draw) {
+        let drawing = if variant.draw_cards() > 0 {
+            format!(" drawing up to {} cards", max_draw)
+        } else {
+            String::new()
+        };
+        return Err(format!(
+            "{} deals at most {} players{}",
+            variant,
+            variant.max_players(max_draw),
+            drawing
+        ));
+    }
+    // The fold model ranks two-card Hold'em starting hands
+    if options.vpip.is_some() && variant != Variant::Holdem {
+        return Err("--vpip only applies to holdem".to_string());
+    }
+    if options.table.is_some() && variant != Variant::Holdem {
+        return Err("--table only applies to holdem".to_string());
+    }
+    if options.max_draw.is_some() && variant.draw_cards() == 0 {
+        return Err(format!("{} has no draw for --max-draw to limit", variant));
+    }
+    if (options.streets || options.transitions.is_some()) && !variant.has_streets() {
+        return Err(format!("{} has no streets to follow hands across", variant));
+    }
+    check_output(options.output)?;
+    let json = options.output == OutputFormat::Json;
+    if json
+        && (options.log_filter.is_some()
+            || options.sample_filter.is_some()
+            || options.streets
+            || options.transitions.is_some()
+            || options.timing
+            || options.profile)
+    {
+        return Err(
+            "--output json only reports the simulation results; it cannot be combined \
+                    with --log, --sample, --streets, --transitions, --timing or --profile"
+                .to_string(),
+        );
+    }
 
-        let winner = simulate_game(num_players, &mut local_hand_rank_counts);
+    let fold_model = match (options.vpip, options.table) {
+        (Some(vpip), _) => Some(FoldModel::new(vpip)),
+        (None, Some(table)) => Some(FoldModel::for_table(table, num_players)),
+        (None, None) => None,
+    };
 
-        // Update wins
-        {
-            let mut wins_lock = wins.lock().unwrap();
-            wins_lock[winner] += 1;
+    // Answer straight from the cache when every query has been computed before
+    let planned_games = options
+        .max_iterations
+        .map_or(num_games, |max| max.min(num_games));
+    let mut cache = if options.no_cache {
+        QueryCache::disabled()
+    } else {
+        QueryCache::open()
+    };
+    let cache_keys: Vec<String> = options
+        .queries
+        .iter()
+        .map(|query| {
+            QueryCache::key(
+                &query.canonical(),
+                num_players,
+                planned_games,
+                fold_model.as_ref(),
+                options.variant,
+                max_draw,
+                options.seed,
+            )
+        })
+        .collect();
+    // Only a run that would report nothing but the queries can skip the simulation
+    let only_queries = !options.queries.is_empty()
+        && options.output == OutputFormat::Text
+        && options.sample_filter.is_none()
+        && options.log_filter.is_none()
+        && options.transitions.is_none()
+        && !options.streets
+        && !options.timing
+        && !options.profile
+        && !options.chunk_stats
+        && options.chunk_csv.is_none()
+        && options.max_seconds.is_none()
+        && !options.converge
+        && options.precision.is_none();
+    let cached: Option<Vec<QueryCounts>> = cache_keys.iter().map(|key| cache.get(key)).collect();
+    if let (true, Some(cached)) = (only_queries, cached) {
+        if let Some(seed) = options.seed {
+            println!("{}", seed_line(seed));
         }
+        print_query_results(&options.queries, &cached);
+        return Ok(());
+    }
 
-        // Update hand rank counts
-        {
-            let mut hand_rank_counts_lock = hand_rank_counts.lock().unwrap();
-            for (key, count) in local_hand_rank_counts {
-                *hand_rank_counts_lock.entry(key).or_insert(0) += count;
+    // Per-hand hooks for everything that needs to see individual games
+    let seed = resolve_seed(options.seed);
+    if !json {
+        println!("{}", seed_line(seed));
+    }
+    let mut hooks: Vec<Box<dyn HandHook>> = Vec::new();
+    if let Some(filter) = &options.log_filter {
+        hooks.push(Box::new(LogHook::new(filter.clone())));
+    }
+    if let Some(filter) = &options.sample_filter {
+        hooks.push(Box::new(SampleHook::new(
+            filter.clone(),
+            options.sample_size,
+            seed,
+        )));
+    }
+    // Statistics over all games, gathered by every worker and merged at the end
+    let mut aggregators: Vec<Box<dyn Aggregator>> = Vec::new();
+    if options.streets {
+        aggregators.push(Box::new(StreetStats::new(variant)));
+    }
+    if let Some(path) = &options.transitions {
+        aggregators.push(Box::new(TransitionStats::new(variant, path.clone())));
+    }
+    // Queries are counted last, so their counts can be taken back off the end
+    aggregators.push(Box::new(QueryStats::new(options.queries.clone())));
+    let hook_refs: Vec<&dyn HandHook> = hooks.iter().map(|hook| hook.as_ref()).collect();
+
+    let mut chunk_log = ChunkLog::new(options.chunk_stats, options.chunk_csv.as_deref())
+        .unwrap_or_else(|err| {
+            eprintln!("error: cannot write chunk log: {}", err);
+            process::exit(1);
+        });
+    let config = SimulationConfig {
+        max_seconds: options.max_seconds,
+        max_iterations: options.max_iterations,
+        tolerance: options
+            .converge
+            .then(|| options.precision.map(|precision| precision / 100.0))
+            .flatten(),
+        timing: options.timing || chunk_log.is_enabled(),
+        paranoid: options.paranoid,
+        profile: options.profile,
+        fold_model: fold_model.clone(),
+        seed,
+        variant,
+        discard_policy: Box::new(StandardDraw::new(max_draw)),
+        ..SimulationConfig::new(num_games, num_players)
+    };
+    // The chunk log prints its own line for every chunk
+    let mut progress = Progress::new(
+        planned_games,
+        options.max_seconds,
+        !options.quiet && !options.chunk_stats,
+    );
+    let mut games_done = 0;
+    let mut stats = simulator::run_simulation(&config, &hook_refs, aggregators, |chunk| {
+        if chunk_log.is_enabled() {
+            if let Err(err) = chunk_log.log(chunk) {
+                eprintln!("warning: could not write chunk log: {}", err);
             }
         }
+        games_done += chunk.games;
+        progress.update(games_done);
     });
+    progress.finish();
+    drop(hook_refs);
+    if let Err(err) = chunk_log.finish() {
+        eprintln!("warning: could not write chunk log: {}", err);
+    }
+    let query_stats = downcast::<QueryStats>(stats.aggregators.pop().unwrap());
+    let query_counts = query_stats.counts();
+    let games_played = stats.games_played;
+    let seats = &stats.seats;
+    let split_pots = stats.split_pots;
+    let hand_rank_counts = &stats.hand_rank_counts;
+    let game_times = &stats.game_times;
+
+    // Results cut short by the budget are cached under the games actually played
+    if !options.queries.is_empty() {
+        for (query, counts) in options.queries.iter().zip(query_counts) {
+            let key = QueryCache::key(
+                &query.canonical(),
+                num_players,
+                games_played,
+                fold_model.as_ref(),
+                options.variant,
+                max_draw,
+                options.seed,
+            );
+            cache.insert(key, *counts);
+        }
+        if let Err(err) = cache.save() {
+            eprintln!("warning: could not save query cache: {}", err);
+        }
+    }
+    warn_precision(options.precision, seats, games_played);
+
+    if json {
+        #[cfg(feature = "json")]
+        {
+            let max_draw = (variant.draw_cards() > 0).then_some(max_draw);
+            let json =
+                report::simulation_json(&config, max_draw, &stats, &options.queries, query_counts);
+            println!("{}", json);
+        }
+        return Ok(());
+    }
+    if options.output == OutputFormat::Csv {
+        let seats: Vec<(String, SeatResults)> = seats
+            .iter()
+            .enumerate()
+            .map(|(seat, results)| ((seat + 1).to_string(), *results))
+            .collect();
+        let dir = &options.output_dir;
+        fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+        let write = |name: &str, contents: &dyn Fn(&mut BufWriter<File>) -> io::Result<()>| {
+            let path = dir.join(name);
+            File::create(&path)
+                .and_then(|file| contents(&mut BufWriter::new(file)))
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            println!("Wrote {}", path.display());
+            Ok::<(), String>(())
+        };
+        write("seats.csv", &|out| {
+            write_seats_csv(out, "seat", &seats, games_played)
+        })?;
+        write("categories.csv", &|out| {
+            write_categories_csv(out, hand_rank_counts, games_played * num_players)
+        })?;
+        finish_reports(&mut hooks, &stats.aggregators);
+        return Ok(());
+    }
+
+    // Display outright wins, ties and total pot equity per seat, each with its standard
+    // error and the equity with its 95% confidence interval
+    let percent = |count: f64| count / games_played.max(1) as f64 * 100.0;
+    let error = |count: usize| {
+        simulator::standard_error(count as f64 / games_played.max(1) as f64, games_played) * 100.0
+    };
+    println!("Seat results (\u{b1} one standard error):");
+    for (i, seat) in seats.iter().enumerate() {
+        let (equity, low, high) = seat.equity_interval(games_played);
+        println!(
+            "Player {}: wins {} ({:.4}% \u{b1} {:.4}), ties {} ({:.4}% \u{b1} {:.4}, average share {:.3}), \
+             equity {:.4}% \u{b1} {:.4} (95% CI {:.4}% to {:.4}%)",
+            i + 1,
+            seat.wins,
+            percent(seat.wins as f64),
+            error(seat.wins),
+            seat.ties,
+            percent(seat.ties as f64),
+            error(seat.ties),
+            seat.average_split(),
+            equity * 100.0,
+            seat.equity_standard_error(games_played) * 100.0,
+            low * 100.0,
+            high * 100.0
+        );
+    }
+    println!(
+        "Split pots: {} ({:.4}%), shared evenly between the tied players",
+        split_pots,
+        split_pots as f64 / games_played.max(1) as f64 * 100.0
+    );
+    if let Some(vpip) = options.vpip {
+        println!(
+            "Preflop folds: each seat plays {:.1}% of hands; the last seat is the big blind and never folds",
+            vpip * 100.0
+        );
+    }
+    if let Some(model) = fold_model.as_ref().filter(|model| model.table().is_some()) {
+        let rates: Vec<String> = (0..num_players - 1)
+            .map(|seat| {
+                format!(
+                    "{} {:.0}%",
+                    position_name(seat, num_players),
+                    model.vpip(seat) * 100.0
+                )
+            })
+            .collect();
+        println!(
+            "Preflop folds at a {} table: seats play {}; the big blind never folds",
+            options.table.unwrap(),
+            rates.join(", ")
+        );
+    }
 
-    // Retrieve the results
-    let wins = Arc::try_unwrap(wins).unwrap().into_inner().unwrap();
-    let hand_rank_counts = Arc::try_unwrap(hand_rank_counts)
-        .unwrap()
-        .into_inner()
-        .unwrap();
+    // Report how precise a run cut short by convergence or the budget is
+    let worst_error = simulator::worst_equity_margin(seats, games_played);
+    if stats.converged {
+        println!(
+            "\nConverged after {} games ({:.1}s); equities are within \u{b1}{:.3}% at 95% confidence",
+            games_played,
+            stats.elapsed.as_secs_f64(),
+            worst_error * 100.0
+        );
+    } else if games_played < num_games {
+        println!(
+            "\nBudget reached after {} of {} games ({:.1}s); equities are within \u{b1}{:.3}% at 95% confidence",
+            games_played,
+            num_games,
+            stats.elapsed.as_secs_f64(),
+            worst_error * 100.0
+        );
+    }
 
-    // Display player wins
-    for (i, &win_count) in wins.iter().enumerate() {
-        println!("Player {} wins {} times", i + 1, win_count);
+    // Display each seat's showdown hand categories
+    println!("\nHand categories per seat:");
+    print!("{:<9}", "");
+    for name in filter::CATEGORIES {
+        print!(" {:>w$}", name, w = name.len().max(7));
+    }
+    println!();
+    for (i, seat) in seats.iter().enumerate() {
+        print!("{:<9}", format!("Player {}", i + 1));
+        for (name, &count) in filter::CATEGORIES.iter().zip(&seat.categories) {
+            let share = format!("{:.3}%", percent(count as f64));
+            print!(" {:>w$}", share, w = name.len().max(7));
+        }
+        println!();
     }
 
     // Display most common hand rank categories
@@ -328,10 +1416,71 @@ fn main() {
     let mut hand_rank_vec: Vec<(&&'static str, &usize)> = hand_rank_counts.iter().collect();
     hand_rank_vec.sort_by(|a, b| b.1.cmp(a.1)); // Sort by frequency descending
 
-    let total_hands = num_games * num_players;
+    let total_hands = games_played * num_players;
 
     for (hand_rank, count) in hand_rank_vec {
         let percentage = (*count as f64 / total_hands as f64) * 100.0;
         println!("{}: {} times ({:.4}%)", hand_rank, count, percentage);
     }
+
+    // Display per-game simulation time percentiles
+    if options.timing && game_times.count() > 0 {
+        let micros = |nanos: u64| nanos as f64 / 1000.0;
+        println!(
+            "\nTime per game: mean {:.1}\u{b5}s, p50 {:.1}\u{b5}s, p90 {:.1}\u{b5}s, p99 {:.1}\u{b5}s, p99.9 {:.1}\u{b5}s",
+            game_times.mean() / 1000.0,
+            micros(game_times.quantile(0.5)),
+            micros(game_times.quantile(0.9)),
+            micros(game_times.quantile(0.99)),
+            micros(game_times.quantile(0.999))
+        );
+    }
+
+    // Display where the time went
+    if let Some(profile) = &stats.profile {
+        profile.report();
+    }
+
+    // Display conditional probability estimates
+    if !options.queries.is_empty() {
+        print_query_results(&options.queries, query_counts);
+    }
+
+    finish_reports(&mut hooks, &stats.aggregators);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_limits() {
+        assert_eq!(parse_seconds("2.5"), Ok(2.5));
+        assert_eq!(parse_seconds("0"), Ok(0.0));
+        for bad in ["-1", "NaN", "inf", "-inf", "soon"] {
+            assert!(parse_seconds(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn trial_counts() {
+        assert_eq!(parse_trials("1"), Ok(1));
+        assert_eq!(parse_trials("100000"), Ok(100_000));
+        for bad in ["0", "-5", "many"] {
+            assert!(parse_trials(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn bad_settings_name_their_line() {
+        let config = Config::parse("threads = 2\nmax_seconds = -1\n").unwrap();
+        let err = with_settings(Cli::command(), "", &config).unwrap_err();
+        assert_eq!(
+            err,
+            "config:2: invalid value '-1' for max-seconds: expected a number of seconds, at least 0"
+        );
+        let config = Config::parse("max_seconds = 1.5").unwrap();
+        assert!(with_settings(Cli::command(), "", &config).is_ok());
+    }
 }
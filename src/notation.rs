@@ -0,0 +1,363 @@
+use crate::{best_five_cards, Card, HandRank, Rank, Suit};
+use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
+
+// Standard card notation: ranks 2-9, T, J, Q, K, A and suits c, d, h, s, so "As",
+// "Td" or "7c". Parsing is case-insensitive and also accepts "10" for ten and the
+// suit symbols, which the alternate format ("{:#}") prints instead of letters.
+
+pub const RANKS: [Rank; 13] = [
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+    Rank::Jack,
+    Rank::Queen,
+    Rank::King,
+    Rank::Ace,
+];
+pub const SUITS: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+const RANK_CHARS: [char; 13] = [
+    '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
+];
+const SUIT_CHARS: [char; 4] = ['c', 'd', 'h', 's'];
+const SUIT_SYMBOLS: [char; 4] = ['\u{2663}', '\u{2666}', '\u{2665}', '\u{2660}'];
+
+pub fn rank_to_char(rank: Rank) -> char {
+    RANK_CHARS[rank as usize - 2]
+}
+
+pub fn suit_to_char(suit: Suit) -> char {
+    SUIT_CHARS[suit as usize]
+}
+
+impl Rank {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rank::Two => "Two",
+            Rank::Three => "Three",
+            Rank::Four => "Four",
+            Rank::Five => "Five",
+            Rank::Six => "Six",
+            Rank::Seven => "Seven",
+            Rank::Eight => "Eight",
+            Rank::Nine => "Nine",
+            Rank::Ten => "Ten",
+            Rank::Jack => "Jack",
+            Rank::Queen => "Queen",
+            Rank::King => "King",
+            Rank::Ace => "Ace",
+        }
+    }
+
+    pub fn plural_name(&self) -> String {
+        match self {
+            Rank::Six => "Sixes".to_string(),
+            rank => format!("{}s", rank.name()),
+        }
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", rank_to_char(*self))
+    }
+}
+
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{}", SUIT_SYMBOLS[*self as usize])
+        } else {
+            write!(f, "{}", suit_to_char(*self))
+        }
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{}{:#}", self.rank, self.suit)
+        } else {
+            write!(f, "{}{}", self.rank, self.suit)
+        }
+    }
+}
+
+// Reads like "Full House, Kings over Tens"
+impl fmt::Display for HandRank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HandRank::HighCard(ranks) => write!(f, "High Card, {}", ranks[0].name()),
+            HandRank::OnePair(pair, _) => write!(f, "One Pair, {}", pair.plural_name()),
+            HandRank::TwoPair(high, low, _) => write!(
+                f,
+                "Two Pair, {} and {}",
+                high.plural_name(),
+                low.plural_name()
+            ),
+            HandRank::ThreeOfAKind(trips, _) => {
+                write!(f, "Three of a Kind, {}", trips.plural_name())
+            }
+            HandRank::Straight(high) => write!(f, "Straight, {} high", high.name()),
+            HandRank::Flush(ranks) => write!(f, "Flush, {} high", ranks[0].name()),
+            HandRank::FullHouse(trips, pair) => write!(
+                f,
+                "Full House, {} over {}",
+                trips.plural_name(),
+                pair.plural_name()
+            ),
+            HandRank::FourOfAKind(quads, _) => {
+                write!(f, "Four of a Kind, {}", quads.plural_name())
+            }
+            HandRank::StraightFlush(high) => write!(f, "Straight Flush, {} high", high.name()),
+            HandRank::RoyalFlush => write!(f, "Royal Flush"),
+        }
+    }
+}
+
+// Cards separated by spaces, as in "Ah Kd 7c"
+pub fn cards_to_string(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(Card::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The best hand in `cards` along with the five cards used, as in
+// "Full House, Kings over Tens (Kh Kd Ks Th Tc)"
+pub fn describe_best_hand(cards: &[Card]) -> String {
+    let (hand_rank, five) = best_five_cards(cards);
+    describe_hand(&hand_rank, &five)
+}
+
+// A hand and the five cards that make it, as in describe_best_hand; for games whose
+// rules pick the five cards differently
+pub fn describe_hand(hand_rank: &HandRank, five: &[Card]) -> String {
+    // Made-hand cards first, then kickers, highest first within each group
+    let group_size = |rank: Rank| five.iter().filter(|card| card.rank == rank).count();
+    let mut ordered = five.to_vec();
+    ordered.sort_by_key(|card| Reverse((group_size(card.rank), card.rank)));
+    format!("{} ({})", hand_rank, cards_to_string(&ordered))
+}
+
+// A hand as its category followed by the rank of every field, made hand first, as in
+// "FullHouse KT", "OnePair QAT4" or "RoyalFlush"; short enough to store in bulk
+pub fn hand_rank_code(hand_rank: &HandRank) -> String {
+    let ranks: Vec<Rank> = match hand_rank {
+        HandRank::HighCard(ranks) | HandRank::Flush(ranks) => ranks.to_vec(),
+        HandRank::OnePair(pair, kickers) => [&[*pair], &kickers[..]].concat(),
+        HandRank::TwoPair(high, low, kicker) => vec![*high, *low, *kicker],
+        HandRank::ThreeOfAKind(trips, kickers) => [&[*trips], &kickers[..]].concat(),
+        HandRank::Straight(high) | HandRank::StraightFlush(high) => vec![*high],
+        HandRank::FullHouse(first, second) | HandRank::FourOfAKind(first, second) => {
+            vec![*first, *second]
+        }
+        HandRank::RoyalFlush => Vec::new(),
+    };
+    let category = crate::hand_rank_category(hand_rank);
+    if ranks.is_empty() {
+        return category.to_string();
+    }
+    let ranks: String = ranks.into_iter().map(rank_to_char).collect();
+    format!("{} {}", category, ranks)
+}
+
+// The inverse of hand_rank_code
+pub fn parse_hand_rank_code(text: &str) -> Result<HandRank, String> {
+    let invalid = || format!("invalid hand rank: {}", text);
+    let (category, ranks) = text.split_once(' ').unwrap_or((text, ""));
+    let ranks: Vec<Rank> = ranks
+        .chars()
+        .map(|c| c.to_string().parse())
+        .collect::<Result<_, String>>()
+        .map_err(|_| invalid())?;
+    let hand_rank = match (category, &ranks[..]) {
+        ("HighCard", &[a, b, c, d, e]) => HandRank::HighCard([a, b, c, d, e]),
+        ("OnePair", &[pair, a, b, c]) => HandRank::OnePair(pair, [a, b, c]),
+        ("TwoPair", &[high, low, kicker]) => HandRank::TwoPair(high, low, kicker),
+        ("ThreeOfAKind", &[trips, a, b]) => HandRank::ThreeOfAKind(trips, [a, b]),
+        ("Straight", &[high]) => HandRank::Straight(high),
+        ("Flush", &[a, b, c, d, e]) => HandRank::Flush([a, b, c, d, e]),
+        ("FullHouse", &[trips, pair]) => HandRank::FullHouse(trips, pair),
+        ("FourOfAKind", &[quads, kicker]) => HandRank::FourOfAKind(quads, kicker),
+        ("StraightFlush", &[high]) => HandRank::StraightFlush(high),
+        ("RoyalFlush", &[]) => HandRank::RoyalFlush,
+        _ => return Err(invalid()),
+    };
+    Ok(hand_rank)
+}
+
+impl FromStr for Rank {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Rank, String> {
+        let mut chars = text.chars();
+        let index = match (chars.next(), chars.next()) {
+            _ if text == "10" => Some(8),
+            (Some(c), None) => RANK_CHARS.iter().position(|&r| r == c.to_ascii_uppercase()),
+            _ => None,
+        };
+        index
+            .map(|index| RANKS[index])
+            .ok_or_else(|| format!("invalid rank: {}", text))
+    }
+}
+
+impl FromStr for Suit {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Suit, String> {
+        let mut chars = text.chars();
+        let index = match (chars.next(), chars.next()) {
+            (Some(c), None) => SUIT_CHARS
+                .iter()
+                .position(|&s| s == c.to_ascii_lowercase())
+                .or_else(|| SUIT_SYMBOLS.iter().position(|&s| s == c)),
+            _ => None,
+        };
+        index
+            .map(|index| SUITS[index])
+            .ok_or_else(|| format!("invalid suit: {}", text))
+    }
+}
+
+impl FromStr for Card {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Card, String> {
+        // The suit is always the last character
+        let invalid = || format!("invalid card: {}", text);
+        let suit_start = text.char_indices().last().ok_or_else(invalid)?.0;
+        let (rank, suit) = text.split_at(suit_start);
+        match (rank.parse(), suit.parse()) {
+            (Ok(rank), Ok(suit)) => Ok(Card::new(rank, suit)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<&str> for Rank {
+    type Error = String;
+
+    fn try_from(text: &str) -> Result<Rank, String> {
+        text.parse()
+    }
+}
+
+impl TryFrom<&str> for Suit {
+    type Error = String;
+
+    fn try_from(text: &str) -> Result<Suit, String> {
+        text.parse()
+    }
+}
+
+impl TryFrom<&str> for Card {
+    type Error = String;
+
+    fn try_from(text: &str) -> Result<Card, String> {
+        text.parse()
+    }
+}
+
+// Parse a hand or board such as "Ah Kd 7c 7s 2d"; cards may also run together, as in
+// "AsKs"
+pub fn parse_cards(text: &str) -> Result<Vec<Card>, String> {
+    let mut cards = Vec::new();
+    for word in text.split_whitespace() {
+        let mut rest = word;
+        while let Some(first) = rest.chars().next() {
+            let rank_len = if rest.starts_with("10") {
+                2
+            } else {
+                first.len_utf8()
+            };
+            let suit_len = rest[rank_len..].chars().next().map_or(0, char::len_utf8);
+            let (card, tail) = rest.split_at(rank_len + suit_len);
+            cards.push(
+                card.parse()
+                    .map_err(|_| format!("invalid card in {}: {}", word, card))?,
+            );
+            rest = tail;
+        }
+    }
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboard::CardSet;
+
+    #[test]
+    fn cards_round_trip() {
+        for card in CardSet::FULL.iter() {
+            let parsed: Card = card.to_string().parse().unwrap();
+            assert_eq!(parsed.index(), card.index());
+            let symbol: Card = format!("{:#}", card).parse().unwrap();
+            assert_eq!(symbol.index(), card.index());
+        }
+    }
+
+    #[test]
+    fn card_spellings() {
+        let text = |cards: Vec<Card>| cards_to_string(&cards);
+        assert_eq!(text(parse_cards("AsKs").unwrap()), "As Ks");
+        assert_eq!(text(parse_cards("10h td Tc").unwrap()), "Th Td Tc");
+        assert_eq!(text(parse_cards("a\u{2660} 7\u{2665}").unwrap()), "As 7h");
+        assert_eq!(text(parse_cards("  ").unwrap()), "");
+    }
+
+    #[test]
+    fn bad_cards() {
+        assert_eq!("1h".parse::<Card>().unwrap_err(), "invalid card: 1h");
+        assert_eq!("Ax".parse::<Card>().unwrap_err(), "invalid card: Ax");
+        assert_eq!("".parse::<Card>().unwrap_err(), "invalid card: ");
+        assert_eq!("11".parse::<Rank>().unwrap_err(), "invalid rank: 11");
+        assert_eq!("cd".parse::<Suit>().unwrap_err(), "invalid suit: cd");
+        assert_eq!(parse_cards("AsK").unwrap_err(), "invalid card in AsK: K");
+        assert_eq!(parse_cards("As Zz").unwrap_err(), "invalid card in Zz: Zz");
+    }
+
+    #[test]
+    fn hand_rank_codes_round_trip() {
+        use Rank::*;
+        let hands = [
+            HandRank::HighCard([Ace, Queen, Nine, Five, Two]),
+            HandRank::OnePair(Queen, [Ace, Ten, Four]),
+            HandRank::TwoPair(King, Ten, Two),
+            HandRank::ThreeOfAKind(Six, [Ace, King]),
+            HandRank::Straight(Five),
+            HandRank::Flush([King, Jack, Nine, Four, Three]),
+            HandRank::FullHouse(King, Ten),
+            HandRank::FourOfAKind(Two, Ace),
+            HandRank::StraightFlush(Nine),
+            HandRank::RoyalFlush,
+        ];
+        for hand in &hands {
+            assert_eq!(&parse_hand_rank_code(&hand_rank_code(hand)).unwrap(), hand);
+        }
+        assert_eq!(hand_rank_code(&hands[6]), "FullHouse KT");
+        assert_eq!(hand_rank_code(&hands[9]), "RoyalFlush");
+        for bad in [
+            "FullHouse K",
+            "FullHouse KTX",
+            "Boat KT",
+            "RoyalFlush A",
+            "",
+        ] {
+            assert_eq!(
+                parse_hand_rank_code(bad).unwrap_err(),
+                format!("invalid hand rank: {}", bad)
+            );
+        }
+    }
+}
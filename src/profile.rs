@@ -0,0 +1,82 @@
+use crate::sketch::Histogram;
+use std::time::Instant;
+
+// Where the time of a game goes, for choosing evaluator backends and parallelism
+// settings. Every phase is timed per game, except evaluation which is timed per player.
+#[derive(Debug, Clone, Copy)]
+pub enum Phase {
+    Shuffle,
+    Deal,
+    Evaluate,
+    Showdown,
+    Aggregate,
+    Hooks,
+}
+
+const PHASES: [(Phase, &str); 6] = [
+    (Phase::Shuffle, "shuffle"),
+    (Phase::Deal, "deal"),
+    (Phase::Evaluate, "evaluate (per player)"),
+    (Phase::Showdown, "showdown"),
+    (Phase::Aggregate, "aggregate"),
+    (Phase::Hooks, "hooks"),
+];
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    phases: Vec<Histogram>,
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        Profile {
+            phases: vec![Histogram::new(); PHASES.len()],
+        }
+    }
+
+    pub fn record(&mut self, phase: Phase, started: Instant) {
+        self.phases[phase as usize].record(started.elapsed().as_nanos() as u64);
+    }
+
+    pub fn merge(&mut self, other: &Profile) {
+        for (phase, other_phase) in self.phases.iter_mut().zip(&other.phases) {
+            phase.merge(other_phase);
+        }
+    }
+
+    pub fn report(&self) {
+        let totals: Vec<f64> = self
+            .phases
+            .iter()
+            .map(|phase| phase.mean() * phase.count() as f64)
+            .collect();
+        let total: f64 = totals.iter().sum();
+        let micros = |nanos: u64| nanos as f64 / 1000.0;
+
+        println!("\nProfile:");
+        println!(
+            "{:<22} {:>10} {:>10} {:>10} {:>10} {:>7}",
+            "Phase", "count", "mean", "p50", "p99", "share"
+        );
+        for (((_, name), phase), phase_total) in PHASES.iter().zip(&self.phases).zip(&totals) {
+            if phase.count() == 0 {
+                continue;
+            }
+            println!(
+                "{:<22} {:>10} {:>8.2}\u{b5}s {:>8.2}\u{b5}s {:>8.2}\u{b5}s {:>6.1}%",
+                name,
+                phase.count(),
+                phase.mean() / 1000.0,
+                micros(phase.quantile(0.5)),
+                micros(phase.quantile(0.99)),
+                phase_total / total.max(1.0) * 100.0
+            );
+        }
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::new()
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant};
+
+// A progress line for long runs on stderr: the share of games played, games per second and
+// the estimated time left, redrawn in place between chunks. It is only drawn when stderr
+// is a terminal, so redirected logs never fill up with redraws.
+pub struct Progress {
+    total: usize,
+    max_seconds: Option<f64>,
+    started: Instant,
+    drawn: Option<Instant>,
+    enabled: bool,
+}
+
+// Least time between two redraws
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Progress {
+    // `total` games are planned, or fewer once `max_seconds` have passed
+    pub fn new(total: usize, max_seconds: Option<f64>, enabled: bool) -> Progress {
+        Progress {
+            total,
+            max_seconds,
+            started: Instant::now(),
+            drawn: None,
+            enabled: enabled && io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn update(&mut self, done: usize) {
+        if !self.enabled
+            || self
+                .drawn
+                .is_some_and(|drawn| drawn.elapsed() < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        self.drawn = Some(Instant::now());
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = done as f64 / elapsed.max(f64::EPSILON);
+        let mut left = self.total.saturating_sub(done) as f64 / rate.max(f64::EPSILON);
+        if let Some(max_seconds) = self.max_seconds {
+            left = left.min((max_seconds - elapsed).max(0.0));
+        }
+        // \x1b[K clears what is left of a longer previous line
+        eprint!(
+            "\r{:5.1}%  {} of {} games  {} games/s  ETA {}\x1b[K",
+            done as f64 / self.total.max(1) as f64 * 100.0,
+            done,
+            self.total,
+            compact(rate),
+            clock(left)
+        );
+    }
+
+    // Clear the line before the results are printed
+    pub fn finish(&mut self) {
+        if self.drawn.is_some() {
+            eprint!("\r\x1b[K");
+        }
+    }
+}
+
+// A rate such as "850", "12.3k" or "4.56M"
+fn compact(rate: f64) -> String {
+    match rate {
+        rate if rate >= 1e6 => format!("{:.2}M", rate / 1e6),
+        rate if rate >= 1e3 => format!("{:.1}k", rate / 1e3),
+        rate => format!("{:.0}", rate),
+    }
+}
+
+// Seconds as "m:ss", or "h:mm:ss" from an hour up
+fn clock(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
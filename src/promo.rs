@@ -0,0 +1,129 @@
+use crate::filter::{category_value, CATEGORIES};
+use crate::hooks::HandHook;
+use crate::{GameResult, HandRank};
+use std::sync::Mutex;
+
+// Casino high-hand promotion: within each time window, the best qualifying hand shown
+// on any table wins the prize, split between tied holders. Every hand reaches
+// showdown in this simulator, so every player's best hand can qualify.
+pub struct PromoSettings {
+    pub tables: usize,
+    pub players_per_table: usize,
+    pub hands_per_hour: usize,
+    pub window_hours: usize,
+    pub windows: usize,
+    // Lowest qualifying category, as an index into CATEGORIES
+    pub qualifier: i64,
+    pub prize: f64,
+}
+
+impl PromoSettings {
+    pub fn hands_per_window(&self) -> usize {
+        self.tables * self.hands_per_hour * self.window_hours
+    }
+
+    pub fn total_hands(&self) -> usize {
+        self.hands_per_window() * self.windows
+    }
+}
+
+// Best qualifying hand of one window and the (table, seat) pairs holding it
+type WindowBest = Option<(HandRank, Vec<(usize, usize)>)>;
+
+pub struct HighHandHook {
+    settings: PromoSettings,
+    best: Mutex<Vec<WindowBest>>,
+}
+
+impl HighHandHook {
+    pub fn new(settings: PromoSettings) -> HighHandHook {
+        let windows = settings.windows;
+        HighHandHook {
+            settings,
+            best: Mutex::new(vec![None; windows]),
+        }
+    }
+}
+
+impl HandHook for HighHandHook {
+    fn on_hand(&self, hand_number: usize, result: &GameResult) {
+        // Hands are dealt window by window, spread evenly over the tables
+        let index = hand_number - 1;
+        let window = index / self.settings.hands_per_window();
+        let table = index % self.settings.tables;
+
+        for (seat, hand_rank) in result.hand_ranks.iter().enumerate() {
+            if category_value(hand_rank) < self.settings.qualifier {
+                continue;
+            }
+            // Qualifying hands are rare, so the lock is rarely taken
+            let mut best_lock = self.best.lock().unwrap();
+            match &mut best_lock[window] {
+                Some((best, holders)) if hand_rank == best => holders.push((table, seat)),
+                Some((best, _)) if hand_rank < best => {}
+                slot => *slot = Some((hand_rank.clone(), vec![(table, seat)])),
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        let settings = &self.settings;
+        let best = self.best.get_mut().unwrap();
+        let awarded = best.iter().filter(|window| window.is_some()).count();
+        let paid = awarded as f64 * settings.prize;
+        let player_hours = (settings.windows
+            * settings.window_hours
+            * settings.tables
+            * settings.players_per_table) as f64;
+
+        println!(
+            "High-hand promotion: {} tables of {}, {} hands/hour, {}h windows, qualifier {}, prize {:.2}",
+            settings.tables,
+            settings.players_per_table,
+            settings.hands_per_hour,
+            settings.window_hours,
+            CATEGORIES[settings.qualifier as usize],
+            settings.prize
+        );
+        println!(
+            "Simulated {} windows ({} hands)",
+            settings.windows,
+            settings.total_hands()
+        );
+        println!(
+            "Windows with a qualifying hand: {:.2}%",
+            awarded as f64 / settings.windows as f64 * 100.0
+        );
+        println!(
+            "Average paid per window: {:.2}",
+            paid / settings.windows as f64
+        );
+        println!("EV per player per hour: {:.4}", paid / player_hours);
+        let split = best
+            .iter()
+            .flatten()
+            .filter(|(_, holders)| holders.len() > 1)
+            .count();
+        if awarded > 0 {
+            println!(
+                "Prizes split between tied hands: {:.2}% of awarded windows",
+                split as f64 / awarded as f64 * 100.0
+            );
+        }
+
+        // Which categories take the prize, so the qualifier can be tuned
+        let mut by_category = [0usize; CATEGORIES.len()];
+        for (hand_rank, _) in best.iter().flatten() {
+            by_category[category_value(hand_rank) as usize] += 1;
+        }
+        for (name, count) in CATEGORIES.iter().zip(by_category) {
+            if count > 0 {
+                println!(
+                    "  won with {}: {:.2}% of windows",
+                    name,
+                    count as f64 / settings.windows as f64 * 100.0
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::aggregate::{downcast, Aggregator};
+use crate::filter::HandFilter;
+use crate::GameResult;
+use std::sync::Arc;
+
+// Conditional probability queries such as "P(hero.win | hero.top_pair)", estimated by
+// counting over simulated games; both sides are filter expressions and the condition
+// is optional
+#[derive(Debug, Clone)]
+pub struct Query {
+    event: HandFilter,
+    condition: Option<HandFilter>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueryCounts {
+    // Games satisfying the condition
+    pub conditioned: usize,
+    // Games satisfying both the condition and the event
+    pub hits: usize,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, String> {
+        let inner = text
+            .trim()
+            .strip_prefix("P(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .ok_or_else(|| format!("expected P(event | condition), got: {}", text))?;
+
+        // The separator is a lone '|', as opposed to the '||' operator
+        let bytes = inner.as_bytes();
+        let separator = (0..bytes.len()).find(|&i| {
+            bytes[i] == b'|' && bytes.get(i + 1) != Some(&b'|') && (i == 0 || bytes[i - 1] != b'|')
+        });
+
+        let query = match separator {
+            Some(i) => Query {
+                event: HandFilter::parse(&inner[..i])?,
+                condition: Some(HandFilter::parse(&inner[i + 1..])?),
+            },
+            None => Query {
+                event: HandFilter::parse(inner)?,
+                condition: None,
+            },
+        };
+        Ok(query)
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.condition {
+            Some(condition) => format!("P({} | {})", self.event.source(), condition.source()),
+            None => format!("P({})", self.event.source()),
+        }
+    }
+
+    // Normalized form used to recognize the same question asked differently
+    pub fn canonical(&self) -> String {
+        match &self.condition {
+            Some(condition) => format!("P({} | {})", self.event.canonical(), condition.canonical()),
+            None => format!("P({})", self.event.canonical()),
+        }
+    }
+
+    pub fn record(&self, result: &GameResult, counts: &mut QueryCounts) {
+        if let Some(condition) = &self.condition {
+            if !condition.matches(result) {
+                return;
+            }
+        }
+        counts.conditioned += 1;
+        if self.event.matches(result) {
+            counts.hits += 1;
+        }
+    }
+}
+
+impl QueryCounts {
+    pub fn merge(&mut self, other: &QueryCounts) {
+        self.conditioned += other.conditioned;
+        self.hits += other.hits;
+    }
+
+    // None until some game satisfies the condition
+    pub fn probability(&self) -> Option<f64> {
+        if self.conditioned == 0 {
+            return None;
+        }
+        Some(self.hits as f64 / self.conditioned as f64)
+    }
+
+    pub fn standard_error(&self) -> Option<f64> {
+        let p = self.probability()?;
+        Some((p * (1.0 - p) / self.conditioned as f64).sqrt())
+    }
+}
+
+// Estimates a set of queries over every simulated hand. Each worker counts into its
+// own copy, so matching a hand takes no lock and no allocation.
+pub struct QueryStats {
+    queries: Arc<[Query]>,
+    counts: Vec<QueryCounts>,
+}
+
+impl QueryStats {
+    pub fn new(queries: Vec<Query>) -> Self {
+        let counts = vec![QueryCounts::default(); queries.len()];
+        QueryStats {
+            queries: queries.into(),
+            counts,
+        }
+    }
+
+    pub fn counts(&self) -> &[QueryCounts] {
+        &self.counts
+    }
+}
+
+impl Aggregator for QueryStats {
+    fn empty(&self) -> Box<dyn Aggregator> {
+        Box::new(QueryStats {
+            queries: Arc::clone(&self.queries),
+            counts: vec![QueryCounts::default(); self.queries.len()],
+        })
+    }
+
+    fn update(&mut self, result: &GameResult) {
+        for (query, counts) in self.queries.iter().zip(&mut self.counts) {
+            query.record(result, counts);
+        }
+    }
+
+    fn merge(&mut self, other: Box<dyn Aggregator>) {
+        let other = downcast::<QueryStats>(other);
+        for (total, counts) in self.counts.iter_mut().zip(&other.counts) {
+            total.merge(counts);
+        }
+    }
+
+    // The caller prints the estimates, next to any cached ones
+    fn report(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate_hand;
+    use crate::notation::parse_cards;
+    use crate::variant::Variant;
+
+    fn showdown(hero: &str, villain: &str, board: &str) -> GameResult {
+        let hole_cards = vec![parse_cards(hero).unwrap(), parse_cards(villain).unwrap()];
+        let board = parse_cards(board).unwrap();
+        let hand_ranks = hole_cards
+            .iter()
+            .map(|hand| evaluate_hand(&[&hand[..], &board[..]].concat()))
+            .collect();
+        GameResult::from_showdown(
+            Variant::Holdem,
+            hole_cards,
+            board,
+            hand_ranks,
+            vec![false; 2],
+        )
+    }
+
+    #[test]
+    fn parse_and_describe() {
+        let query = Query::parse("P(hero.win | players_in >= 2 || hero.suited)").unwrap();
+        assert_eq!(
+            query.describe(),
+            "P(hero.win | players_in >= 2 || hero.suited)"
+        );
+        assert!(Query::parse("hero.win").is_err());
+    }
+
+    #[test]
+    fn worker_copies_merge() {
+        let queries = vec![
+            Query::parse("P(hero.win | hero.top_pair)").unwrap(),
+            Query::parse("P(hero.win)").unwrap(),
+        ];
+        let games = [
+            showdown("As Kd", "Qs Qd", "Ac 7h 2d 9s 3c"),
+            showdown("As Kd", "Qs Qd", "Qc 7h 2d 9s 3c"),
+            showdown("Js Td", "Qs Qd", "Jc 7h 2d 9s 3c"),
+        ];
+        let stats = QueryStats::new(queries);
+        let mut first = stats.empty();
+        let mut second = stats.empty();
+        first.update(&games[0]);
+        second.update(&games[1]);
+        second.update(&games[2]);
+        first.merge(second);
+        let counts = downcast::<QueryStats>(first).counts().to_vec();
+        assert_eq!((counts[0].conditioned, counts[0].hits), (2, 1));
+        assert_eq!((counts[1].conditioned, counts[1].hits), (3, 1));
+        assert_eq!(counts[0].probability(), Some(0.5));
+        assert_eq!(QueryCounts::default().probability(), None);
+        assert_eq!(QueryCounts::default().standard_error(), None);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::bitboard::CardSet;
+use crate::callers::parse_class;
+use crate::notation::{cards_to_string, parse_cards, RANKS, SUITS};
+use crate::{Card, Rank};
+use itertools::Itertools;
+use std::fmt;
+
+// A set of two-card hands an opponent may hold, written as comma-separated terms in the
+// usual notation: classes such as "QQ", "AKs", "AKo" or "AK" (both), "QQ+" for queens or
+// better, "ATs+" for ATs through AKs, dashed spans such as "22-55" or "A2s-A5s", and
+// exact combos such as "AsKs", with no hand named by two terms. Every combo in the range
+// is as likely as any other, so offsuit classes weigh three times as much as suited ones.
+#[derive(Debug, Clone)]
+pub struct Range {
+    combos: Vec<[Card; 2]>,
+    source: String,
+}
+
+// A starting hand class; `suited` is None for both suited and offsuit hands
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Class {
+    high: Rank,
+    low: Rank,
+    suited: Option<bool>,
+}
+
+impl Class {
+    fn parse(text: &str) -> Option<Class> {
+        let (high, low, suited) = parse_class(text).ok()?;
+        Some(Class { high, low, suited })
+    }
+
+    fn is_pair(&self) -> bool {
+        self.high == self.low
+    }
+
+    fn with_ranks(&self, high: Rank, low: Rank) -> Class {
+        Class { high, low, ..*self }
+    }
+
+    fn combos(&self) -> impl Iterator<Item = [Card; 2]> + '_ {
+        SUITS
+            .iter()
+            .cartesian_product(SUITS)
+            .map(|(&high, low)| [Card::new(self.high, high), Card::new(self.low, low)])
+            .filter(move |[high, low]| {
+                if self.is_pair() {
+                    high.suit < low.suit
+                } else {
+                    self.suited
+                        .is_none_or(|suited| (high.suit == low.suit) == suited)
+                }
+            })
+    }
+}
+
+// Ranks from `low` to `high` inclusive
+fn ranks_between(low: Rank, high: Rank) -> impl Iterator<Item = Rank> {
+    RANKS
+        .into_iter()
+        .filter(move |&rank| low <= rank && rank <= high)
+}
+
+impl Range {
+    pub fn parse(text: &str) -> Result<Range, String> {
+        let mut classes = Vec::new();
+        let mut exact = Vec::new();
+        for term in text.split(',').map(str::trim) {
+            let invalid = || format!("invalid range term: {}", term);
+            if let Some(start) = term.strip_suffix('+') {
+                // Pairs climb to aces, other hands climb the kicker to one below the top card
+                let class = Class::parse(start).ok_or_else(invalid)?;
+                if class.is_pair() {
+                    classes.extend(
+                        ranks_between(class.low, Rank::Ace)
+                            .map(|rank| class.with_ranks(rank, rank)),
+                    );
+                } else {
+                    let top = RANKS[class.high as usize - 3];
+                    classes.extend(
+                        ranks_between(class.low, top).map(|low| class.with_ranks(class.high, low)),
+                    );
+                }
+            } else if let Some((from, to)) = term.split_once('-') {
+                let (from, to) = match (Class::parse(from), Class::parse(to)) {
+                    (Some(from), Some(to)) => (from, to),
+                    _ => return Err(invalid()),
+                };
+                if from.is_pair() && to.is_pair() {
+                    let (low, high) = (from.low.min(to.low), from.low.max(to.low));
+                    classes
+                        .extend(ranks_between(low, high).map(|rank| from.with_ranks(rank, rank)));
+                } else if from.high == to.high && from.suited == to.suited && !from.is_pair() {
+                    let (low, high) = (from.low.min(to.low), from.low.max(to.low));
+                    classes.extend(
+                        ranks_between(low, high).map(|low| from.with_ranks(from.high, low)),
+                    );
+                } else {
+                    return Err(invalid());
+                }
+            } else if let Some(class) = Class::parse(term) {
+                classes.push(class);
+            } else {
+                match parse_cards(term).ok().as_deref() {
+                    Some(&[a, b]) if a.index() != b.index() => exact.push([a, b]),
+                    _ => return Err(invalid()),
+                }
+            }
+        }
+
+        // Terms that overlap would weigh their shared hands twice
+        let combos: Vec<[Card; 2]> = classes
+            .iter()
+            .flat_map(Class::combos)
+            .chain(exact)
+            .collect();
+        let mut twice = combos
+            .iter()
+            .duplicates_by(|combo| CardSet::from(&combo[..]));
+        if let Some(combo) = twice.next() {
+            return Err(format!("{} is in the range twice", cards_to_string(combo)));
+        }
+        Ok(Range {
+            combos,
+            source: text.trim().to_string(),
+        })
+    }
+
+    // Every hand in the range, before removing any known cards
+    pub fn combos(&self) -> &[[Card; 2]] {
+        &self.combos
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combos(text: &str) -> usize {
+        Range::parse(text).unwrap().combos().len()
+    }
+
+    fn hands(text: &str) -> Vec<String> {
+        Range::parse(text)
+            .unwrap()
+            .combos()
+            .iter()
+            .map(|combo| cards_to_string(combo))
+            .collect()
+    }
+
+    #[test]
+    fn known_counts() {
+        assert_eq!(combos("QQ+"), 18);
+        assert_eq!(combos("AKs"), 4);
+        assert_eq!(combos("AKo"), 12);
+        assert_eq!(combos("QQ+,AKs"), 22);
+        assert_eq!(combos("QQ+, AQs+, JTs"), 30);
+        assert_eq!(combos("22-55"), 24);
+        assert_eq!(combos("AsKs, 7h2c"), 2);
+    }
+
+    #[test]
+    fn expansions() {
+        // A2s through AKs, every suit of each
+        let suited = hands("A2s+");
+        assert_eq!(suited.len(), 48);
+        assert_eq!(suited[..4], ["Ac 2c", "Ad 2d", "Ah 2h", "As 2s"]);
+        assert!(suited.iter().any(|hand| hand == "As Ks"));
+        assert_eq!(hands("A2s-A5s"), hands("A2s,A3s,A4s,A5s"));
+
+        let offsuit = hands("T9o");
+        assert_eq!(offsuit.len(), 12);
+        assert!(offsuit.iter().all(|hand| hand.starts_with('T')));
+        assert!(offsuit
+            .iter()
+            .all(|hand| hand.as_bytes()[1] != hand.as_bytes()[4]));
+        assert_eq!(Range::parse(" QQ+ ").unwrap().to_string(), "QQ+");
+    }
+
+    #[test]
+    fn malformed() {
+        let error = |text: &str| Range::parse(text).unwrap_err();
+        assert_eq!(error("AKx"), "invalid range term: AKx");
+        assert_eq!(error("QQ-"), "invalid range term: QQ-");
+        assert_eq!(error("AKs-QJs"), "invalid range term: AKs-QJs");
+        assert_eq!(error("QQ+,"), "invalid range term: ");
+        assert_eq!(error("AsAs"), "invalid range term: AsAs");
+        assert_eq!(error("QQ+,KK"), "Kc Kd is in the range twice");
+        assert_eq!(error("AKs,AsKs"), "As Ks is in the range twice");
+    }
+}
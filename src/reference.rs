@@ -0,0 +1,51 @@
+use crate::Card;
+use itertools::Itertools;
+
+// Deliberately simple, kicker-aware evaluator used as ground truth. Values compare by
+// category first (0 = high card ... 8 = straight flush) and then by the ranks that
+// break ties, in the order they matter.
+pub type ReferenceValue = (u8, Vec<u8>);
+
+fn reference_five(cards: &[&Card]) -> ReferenceValue {
+    // Group ranks by multiplicity: bigger groups first, higher ranks first within a size
+    let mut groups: Vec<(usize, u8)> = cards
+        .iter()
+        .map(|card| card.rank as u8)
+        .counts()
+        .into_iter()
+        .map(|(rank, count)| (count, rank))
+        .collect();
+    groups.sort_unstable_by(|a, b| b.cmp(a));
+    let ranks: Vec<u8> = groups.iter().map(|&(_, rank)| rank).collect();
+
+    let is_flush = cards.iter().all(|card| card.suit == cards[0].suit);
+    let straight_high = if groups.len() == 5 && ranks[0] - ranks[4] == 4 {
+        Some(ranks[0])
+    } else if ranks == [14, 5, 4, 3, 2] {
+        Some(5)
+    } else {
+        None
+    };
+
+    match (straight_high, is_flush, groups[0].0, groups[1].0) {
+        (Some(high), true, _, _) => (8, vec![high]),
+        (_, _, 4, _) => (7, ranks),
+        (_, _, 3, 2) => (6, ranks),
+        (_, true, _, _) => (5, ranks),
+        (Some(high), false, _, _) => (4, vec![high]),
+        (_, _, 3, _) => (3, ranks),
+        (_, _, 2, 2) => (2, ranks),
+        (_, _, 2, _) => (1, ranks),
+        _ => (0, ranks),
+    }
+}
+
+// Best value over every five-card subset
+pub fn reference_value(cards: &[Card]) -> ReferenceValue {
+    cards
+        .iter()
+        .combinations(5)
+        .map(|combo| reference_five(&combo))
+        .max()
+        .unwrap()
+}
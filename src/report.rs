@@ -0,0 +1,242 @@
+use crate::filter::CATEGORIES;
+use crate::query::{Query, QueryCounts};
+use crate::simulator::{self, SeatResults, SimulationConfig, SimulationStats};
+use serde::{Serialize, Serializer};
+
+// Results as structured JSON for scripts and dashboards. Every document carries the
+// metadata needed to tell where it came from: the schema version, which goes up whenever
+// a field changes meaning or goes away, the crate version, the evaluator and the seed.
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+// The hand evaluator behind every showdown, see lookup.rs
+pub const EVALUATOR: &str = "lookup";
+
+// Counts per hand category as a JSON object, weakest category first
+type CategoryCounts = Vec<(&'static str, usize)>;
+
+fn in_order<S: Serializer>(counts: &CategoryCounts, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(counts.iter().copied())
+}
+
+#[derive(Serialize)]
+struct Metadata {
+    schema_version: u32,
+    crate_version: &'static str,
+    evaluator: &'static str,
+}
+
+impl Metadata {
+    fn new() -> Metadata {
+        Metadata {
+            schema_version: SCHEMA_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION"),
+            evaluator: EVALUATOR,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RunConfig {
+    games: usize,
+    players: usize,
+    variant: &'static str,
+    // Set when every seat plays the same share of hands
+    vpip: Option<f64>,
+    // Set when the share depends on position, see folding.rs
+    table: Option<&'static str>,
+    max_draw: Option<usize>,
+    max_seconds: Option<f64>,
+    max_iterations: Option<usize>,
+    // The equity margin a converging run stops at, as a share
+    tolerance: Option<f64>,
+}
+
+// Shares are of all games played, e.g. equity 0.5 for half the pots, with their standard
+// errors and the equity's 95% confidence interval
+#[derive(Serialize)]
+struct SeatReport {
+    seat: usize,
+    wins: usize,
+    ties: usize,
+    win_rate: f64,
+    win_rate_error: f64,
+    tie_rate: f64,
+    tie_rate_error: f64,
+    average_split: f64,
+    equity: f64,
+    equity_error: f64,
+    equity_interval: [f64; 2],
+    // Showdown hands per category
+    #[serde(serialize_with = "in_order")]
+    categories: CategoryCounts,
+}
+
+impl SeatReport {
+    fn new(seat: usize, results: &SeatResults, games: usize) -> SeatReport {
+        let share = |count: usize| count as f64 / games.max(1) as f64;
+        let (equity, low, high) = results.equity_interval(games);
+        SeatReport {
+            seat: seat + 1,
+            wins: results.wins,
+            ties: results.ties,
+            win_rate: share(results.wins),
+            win_rate_error: simulator::standard_error(share(results.wins), games),
+            tie_rate: share(results.ties),
+            tie_rate_error: simulator::standard_error(share(results.ties), games),
+            average_split: results.average_split(),
+            equity,
+            equity_error: results.equity_standard_error(games),
+            equity_interval: [low, high],
+            categories: CATEGORIES.iter().copied().zip(results.categories).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct QueryReport {
+    query: String,
+    // null when no game satisfied the condition
+    probability: Option<f64>,
+    standard_error: Option<f64>,
+    conditioned: usize,
+    hits: usize,
+}
+
+#[derive(Serialize)]
+struct SimulationReport {
+    #[serde(flatten)]
+    metadata: Metadata,
+    seed: u64,
+    config: RunConfig,
+    games_played: usize,
+    converged: bool,
+    elapsed_seconds: f64,
+    seats: Vec<SeatReport>,
+    split_pots: usize,
+    // Showdown hands per category over every seat
+    #[serde(serialize_with = "in_order")]
+    hand_categories: CategoryCounts,
+    queries: Vec<QueryReport>,
+}
+
+// A finished simulation as one JSON document. `max_draw` is the draw limit in games with a
+// draw, as the discard policy does not expose it.
+pub fn simulation_json(
+    config: &SimulationConfig,
+    max_draw: Option<usize>,
+    stats: &SimulationStats,
+    queries: &[Query],
+    query_counts: &[QueryCounts],
+) -> String {
+    let report = SimulationReport {
+        metadata: Metadata::new(),
+        seed: config.seed,
+        config: RunConfig {
+            games: config.num_games,
+            players: config.num_players,
+            variant: config.variant.name(),
+            vpip: config
+                .fold_model
+                .as_ref()
+                .filter(|model| model.table().is_none())
+                .map(|model| model.vpip(0)),
+            table: config
+                .fold_model
+                .as_ref()
+                .and_then(|model| model.table())
+                .map(|table| table.name()),
+            max_draw,
+            max_seconds: config.max_seconds,
+            max_iterations: config.max_iterations,
+            tolerance: config.tolerance,
+        },
+        games_played: stats.games_played,
+        converged: stats.converged,
+        elapsed_seconds: stats.elapsed.as_secs_f64(),
+        seats: stats
+            .seats
+            .iter()
+            .enumerate()
+            .map(|(seat, results)| SeatReport::new(seat, results, stats.games_played))
+            .collect(),
+        split_pots: stats.split_pots,
+        hand_categories: CATEGORIES
+            .iter()
+            .map(|&category| {
+                let count = stats.hand_rank_counts.get(category).copied();
+                (category, count.unwrap_or(0))
+            })
+            .collect(),
+        queries: queries
+            .iter()
+            .zip(query_counts)
+            .map(|(query, counts)| QueryReport {
+                query: query.canonical(),
+                probability: counts.probability(),
+                standard_error: counts.standard_error(),
+                conditioned: counts.conditioned,
+                hits: counts.hits,
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&report).unwrap()
+}
+
+// The errors and interval are left out of exact results
+#[derive(Serialize)]
+struct EquitySeat<'a> {
+    hand: &'a str,
+    win: f64,
+    tie: f64,
+    equity: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    equity_error: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    equity_interval: Option<[f64; 2]>,
+}
+
+#[derive(Serialize)]
+struct EquityReport<'a> {
+    #[serde(flatten)]
+    metadata: Metadata,
+    // None for exact results, which need no seed
+    seed: Option<u64>,
+    exact: bool,
+    deals: usize,
+    board: String,
+    seats: Vec<EquitySeat<'a>>,
+}
+
+// An equity calculation as one JSON document: each player's name, e.g. "AsKs", "QQ+" or
+// "Random 1", with their results over `deals` deals, and the known board
+pub fn equity_json(
+    seed: Option<u64>,
+    deals: usize,
+    board: &str,
+    seats: &[(String, SeatResults)],
+) -> String {
+    let share = |count: f64| count / deals.max(1) as f64;
+    let report = EquityReport {
+        metadata: Metadata::new(),
+        seed,
+        exact: seed.is_none(),
+        deals,
+        board: board.to_string(),
+        seats: seats
+            .iter()
+            .map(|(hand, results)| {
+                let (_, low, high) = results.equity_interval(deals);
+                EquitySeat {
+                    hand,
+                    win: share(results.wins as f64),
+                    tie: share(results.ties as f64),
+                    equity: share(results.equity()),
+                    equity_error: seed.map(|_| results.equity_standard_error(deals)),
+                    equity_interval: seed.map(|_| [low, high]),
+                }
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&report).unwrap()
+}
@@ -0,0 +1,42 @@
+use rand::Rng;
+
+// Uniform fixed-size sample over a stream of unknown length. Every item draws a random
+// priority and the lowest priorities are kept, so when each item's priority comes from
+// its own seeded stream the sample does not depend on the order items arrive in.
+#[derive(Debug)]
+pub struct Reservoir<T> {
+    capacity: usize,
+    seen: usize,
+    items: Vec<(u64, T)>,
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(capacity: usize) -> Self {
+        Reservoir {
+            capacity,
+            seen: 0,
+            items: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn offer<R: Rng>(&mut self, item: T, rng: &mut R) {
+        self.seen += 1;
+        let priority = rng.gen::<u64>();
+        if self.items.len() < self.capacity {
+            self.items.push((priority, item));
+        } else if let Some(highest) = self.items.iter_mut().max_by_key(|(p, _)| *p) {
+            if priority < highest.0 {
+                *highest = (priority, item);
+            }
+        }
+    }
+
+    // Number of items offered so far, kept or not
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    pub fn into_items(self) -> Vec<T> {
+        self.items.into_iter().map(|(_, item)| item).collect()
+    }
+}
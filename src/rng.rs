@@ -0,0 +1,31 @@
+use rand::rngs::OsRng;
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+// Every random draw comes from a ChaCha20 stream derived from one master seed, what
+// the stream is for and a task number such as the first game of a batch. The same seed
+// therefore reproduces a run exactly, however the batches are spread over threads.
+pub type SimRng = ChaCha20Rng;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Purpose {
+    // Shuffling and preflop fold decisions
+    Deal = 0,
+    // Choosing which matching hands to sample
+    Sample = 1,
+}
+
+pub fn task_rng(seed: u64, purpose: Purpose, task: u64) -> SimRng {
+    let mut key = [0u8; 32];
+    key[..8].copy_from_slice(&seed.to_le_bytes());
+    key[8] = purpose as u8;
+    let mut rng = SimRng::from_seed(key);
+    rng.set_stream(task);
+    rng
+}
+
+// A fresh master seed from OS entropy, for runs that were not given one; report it so the
+// run can be repeated with the same seed
+pub fn random_seed() -> u64 {
+    OsRng.next_u64()
+}
@@ -0,0 +1,190 @@
+use crate::filter::CATEGORIES;
+use crate::low::LowHandRank;
+use crate::notation::{hand_rank_code, parse_hand_rank_code};
+use crate::variant::Variant;
+use crate::{Card, HandRank, Rank, Suit};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::str::FromStr;
+
+// Serde support behind the `serde` feature. Cards, ranks and suits are written in card
+// notation ("As", "A", "s"), hand ranks as their codes ("FullHouse KT"), lows as
+// "8-6-4-2-A" and variants by name, so saved results stay readable and read back through
+// the same parsers as the command line.
+
+fn to_text<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+fn from_text<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr<Err = String>,
+    D: Deserializer<'de>,
+{
+    let text = Cow::<str>::deserialize(deserializer)?;
+    text.parse().map_err(D::Error::custom)
+}
+
+impl Serialize for Rank {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        to_text(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rank {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rank, D::Error> {
+        from_text(deserializer)
+    }
+}
+
+impl Serialize for Suit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        to_text(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Suit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Suit, D::Error> {
+        from_text(deserializer)
+    }
+}
+
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        to_text(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Card, D::Error> {
+        from_text(deserializer)
+    }
+}
+
+impl Serialize for HandRank {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hand_rank_code(self))
+    }
+}
+
+impl<'de> Deserialize<'de> for HandRank {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<HandRank, D::Error> {
+        let text = Cow::<str>::deserialize(deserializer)?;
+        parse_hand_rank_code(&text).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for LowHandRank {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        to_text(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LowHandRank {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<LowHandRank, D::Error> {
+        from_text(deserializer)
+    }
+}
+
+impl Serialize for Variant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Variant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Variant, D::Error> {
+        from_text(deserializer)
+    }
+}
+
+// Counts indexed by filter::category_value as an object keyed by category name, weakest
+// first; categories left out read back as zero
+pub(crate) mod category_counts {
+    use super::*;
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        counts: &[usize; 10],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(CATEGORIES.iter().zip(counts))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[usize; 10], D::Error> {
+        let mut counts = [0; 10];
+        for (category, count) in HashMap::<Cow<str>, usize>::deserialize(deserializer)? {
+            let index = CATEGORIES
+                .iter()
+                .position(|&name| name == category)
+                .ok_or_else(|| D::Error::custom(format!("unknown hand category: {}", category)))?;
+            counts[index] = count;
+        }
+        Ok(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notation::parse_cards;
+    use crate::query::QueryCounts;
+    use crate::variant::Variant;
+    use crate::{evaluate_hand, GameResult};
+
+    #[test]
+    fn game_result_round_trip() {
+        let hole_cards = vec![
+            parse_cards("As 2d 7c Kh").unwrap(),
+            parse_cards("Ks Kd 3c 4h").unwrap(),
+        ];
+        let board = parse_cards("Ac 5c 6d 7h Ks").unwrap();
+        let hand_ranks = hole_cards
+            .iter()
+            .map(|hand| evaluate_hand(&[&hand[..], &board[..]].concat()))
+            .collect();
+        let result = GameResult::from_showdown(
+            Variant::OmahaHiLo,
+            hole_cards,
+            board,
+            hand_ranks,
+            vec![false; 2],
+        );
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains(r#""variant":"omaha8""#), "{}", json);
+        assert!(
+            json.contains(r#""board":["Ac","5c","6d","7h","Ks"]"#),
+            "{}",
+            json
+        );
+        let read: GameResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&read).unwrap(), json);
+        assert_eq!(read.variant, result.variant);
+        assert_eq!(read.hand_ranks, result.hand_ranks);
+        assert_eq!(read.low_hands, result.low_hands);
+        assert_eq!(read.winners, result.winners);
+        assert_eq!(read.low_winners, result.low_winners);
+    }
+
+    #[test]
+    fn query_counts_round_trip() {
+        let counts = QueryCounts {
+            conditioned: 120,
+            hits: 45,
+        };
+        let json = serde_json::to_string(&counts).unwrap();
+        assert_eq!(json, r#"{"conditioned":120,"hits":45}"#);
+        let read: QueryCounts = serde_json::from_str(&json).unwrap();
+        assert_eq!((read.conditioned, read.hits), (120, 45));
+    }
+
+    #[test]
+    fn bad_text_is_an_error() {
+        assert!(serde_json::from_str::<GameResult>(r#"{"variant":"omaha9"}"#).is_err());
+        assert!(serde_json::from_str::<crate::low::LowHandRank>(r#""9-4-3-2-A""#).is_err());
+        assert!(serde_json::from_str::<crate::low::LowHandRank>(r#""8-4-4-2-A""#).is_err());
+    }
+}
@@ -0,0 +1,411 @@
+use crate::aggregate::Aggregator;
+use crate::alloc_counter;
+use crate::batch::GameBatch;
+use crate::budget::Budget;
+use crate::chunk_log::ChunkStats;
+use crate::draw::{DiscardPolicy, StandardDraw};
+use crate::filter::category_value;
+use crate::folding::FoldModel;
+use crate::hooks::HandHook;
+use crate::profile::{Phase, Profile};
+use crate::rng::{random_seed, task_rng, Purpose};
+use crate::sketch::Histogram;
+use crate::variant::Variant;
+use crate::GameResult;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// Games dealt together into one GameBatch
+const BATCH_SIZE: usize = 256;
+
+// Games played between two budget checks
+pub const CHUNK_SIZE: usize = 4096;
+
+// Games a run with a tolerance plays before it may stop
+pub const MIN_CONVERGENCE_GAMES: usize = 4 * CHUNK_SIZE;
+
+// Set by `set_threads(1)`: batches then run in order on the calling thread, without rayon
+static SERIAL: AtomicBool = AtomicBool::new(false);
+
+// Split `games` into batches and fold each into an accumulator made by `identity` with
+// `f`, merging the accumulators with `merge`. Built with `parallel`, every rayon task
+// folds into its own accumulator, so batches never contend for a lock. Otherwise, on one
+// thread or on wasm, which has no threads to spawn, the same batches are folded in order
+// into a single accumulator; every batch seeds its own random numbers, so the games
+// played are the same either way.
+pub fn fold_batches<T, I, F, M>(games: Range<usize>, identity: I, f: F, merge: M) -> T
+where
+    T: Send,
+    I: Fn() -> T + Sync + Send,
+    F: Fn(T, Range<usize>) -> T + Sync + Send,
+    M: Fn(T, T) -> T + Sync + Send,
+{
+    let batches: Vec<Range<usize>> = games
+        .clone()
+        .step_by(BATCH_SIZE)
+        .map(|start| start..(start + BATCH_SIZE).min(games.end))
+        .collect();
+    #[cfg(feature = "parallel")]
+    if !SERIAL.load(Ordering::Relaxed) && !cfg!(target_arch = "wasm32") {
+        return batches
+            .into_par_iter()
+            .fold(&identity, f)
+            .reduce(&identity, merge);
+    }
+    // A single accumulator, so there is nothing to merge
+    let _ = merge;
+    batches.into_iter().fold(identity(), f)
+}
+
+// Size the thread pool simulations run on; must be called before the first simulation
+pub fn set_threads(threads: usize) -> Result<(), String> {
+    if threads == 0 {
+        return Err("at least one thread is needed".to_string());
+    }
+    if threads == 1 {
+        SERIAL.store(true, Ordering::Relaxed);
+        return Ok(());
+    }
+    #[cfg(feature = "parallel")]
+    return rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .map_err(|err| err.to_string());
+    #[cfg(not(feature = "parallel"))]
+    Err("built without the parallel feature, so only one thread is available".to_string())
+}
+
+pub struct SimulationConfig {
+    pub num_games: usize,
+    pub num_players: usize,
+    pub max_seconds: Option<f64>,
+    pub max_iterations: Option<usize>,
+    // Stop early once every seat's equity is within this share at 95% confidence, e.g.
+    // 0.001 for a tenth of a percent; checked between chunks
+    pub tolerance: Option<f64>,
+    // Record per-game simulation times into `SimulationStats::game_times`
+    pub timing: bool,
+    // Check every deal for duplicate cards, as debug builds always do
+    pub paranoid: bool,
+    // Time every phase of every game into `SimulationStats::profile`
+    pub profile: bool,
+    // Fold weak hands preflop instead of taking every seat to showdown
+    pub fold_model: Option<FoldModel>,
+    // Master seed every batch derives its random numbers from
+    pub seed: u64,
+    pub variant: Variant,
+    // How players draw in games with a draw
+    pub discard_policy: Box<dyn DiscardPolicy>,
+}
+
+impl SimulationConfig {
+    pub fn new(num_games: usize, num_players: usize) -> Self {
+        SimulationConfig {
+            num_games,
+            num_players,
+            max_seconds: None,
+            max_iterations: None,
+            tolerance: None,
+            timing: false,
+            paranoid: false,
+            profile: false,
+            fold_model: None,
+            seed: random_seed(),
+            variant: Variant::Holdem,
+            discard_policy: Box::new(StandardDraw::default()),
+        }
+    }
+}
+
+// Standard errors from the mean to either end of a 95% confidence interval
+pub const Z_95: f64 = 1.96;
+
+// Binomial standard error of a share `p` estimated over `trials`
+pub fn standard_error(p: f64, trials: usize) -> f64 {
+    (p * (1.0 - p) / trials.max(1) as f64).sqrt()
+}
+
+// The widest 95% confidence margin of any seat's equity share over `games` games
+pub fn worst_equity_margin(seats: &[SeatResults], games: usize) -> f64 {
+    seats
+        .iter()
+        .map(|seat| Z_95 * seat.equity_standard_error(games))
+        .fold(0.0, f64::max)
+}
+
+// One seat's results over many games: outright wins, split pots with the share of the
+// pot they brought in, and the showdown hand categories the seat held. Winning either
+// half of a hi-lo pot without the other is a split pot.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeatResults {
+    pub wins: usize,
+    pub ties: usize,
+    // Pots won through ties, e.g. 0.5 for a two-way split
+    pub tie_equity: f64,
+    // Sum of the squared share of every pot won, for the variance of equity
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub equity_squares: f64,
+    // Indexed by filter::category_value; serialized by category name
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::category_counts"))]
+    pub categories: [usize; 10],
+}
+
+impl SeatResults {
+    pub fn record(&mut self, result: &GameResult, seat: usize) {
+        let share = result.equity(seat);
+        if share > 0.0 {
+            self.equity_squares += share * share;
+            if result.is_tie() {
+                self.ties += 1;
+                self.tie_equity += share;
+            } else {
+                self.wins += 1;
+            }
+        }
+        self.categories[category_value(&result.hand_ranks[seat]) as usize] += 1;
+    }
+
+    pub fn merge(&mut self, other: &SeatResults) {
+        self.wins += other.wins;
+        self.ties += other.ties;
+        self.tie_equity += other.tie_equity;
+        self.equity_squares += other.equity_squares;
+        for (total, count) in self.categories.iter_mut().zip(&other.categories) {
+            *total += count;
+        }
+    }
+
+    // Outright wins of `games` games that were never dealt out, so they add no hand
+    // categories
+    pub fn record_wins(&mut self, games: usize) {
+        self.wins += games;
+        self.equity_squares += games as f64;
+    }
+
+    // Pots won, with split pots shared evenly between their winners
+    pub fn equity(&self) -> f64 {
+        self.wins as f64 + self.tie_equity
+    }
+
+    // Standard error of the equity share over `games` games, from the variance of the
+    // pot share won per game, so split pots count for what they paid
+    pub fn equity_standard_error(&self, games: usize) -> f64 {
+        let games = games.max(1) as f64;
+        let mean = self.equity() / games;
+        let variance = (self.equity_squares / games - mean * mean).max(0.0);
+        (variance / games).sqrt()
+    }
+
+    // The equity share over `games` games with its 95% confidence interval
+    pub fn equity_interval(&self, games: usize) -> (f64, f64, f64) {
+        let share = self.equity() / games.max(1) as f64;
+        let margin = Z_95 * self.equity_standard_error(games);
+        (share, (share - margin).max(0.0), (share + margin).min(1.0))
+    }
+
+    // Average share of the pot in the pots this seat split
+    pub fn average_split(&self) -> f64 {
+        if self.ties == 0 {
+            return 0.0;
+        }
+        self.tie_equity / self.ties as f64
+    }
+}
+
+// Running statistics of a simulation: each rayon task keeps its own and they are merged
+// once the task is done
+struct Totals {
+    seats: Vec<SeatResults>,
+    split_pots: usize,
+    hand_rank_counts: HashMap<&'static str, usize>,
+    game_times: Histogram,
+    profile: Option<Profile>,
+    aggregators: Vec<Box<dyn Aggregator>>,
+}
+
+impl Totals {
+    fn new(num_players: usize, profile: bool, aggregators: &[Box<dyn Aggregator>]) -> Totals {
+        Totals {
+            seats: vec![SeatResults::default(); num_players],
+            split_pots: 0,
+            hand_rank_counts: HashMap::new(),
+            game_times: Histogram::new(),
+            profile: profile.then(Profile::new),
+            aggregators: aggregators
+                .iter()
+                .map(|aggregator| aggregator.empty())
+                .collect(),
+        }
+    }
+
+    fn record(&mut self, result: &GameResult) {
+        for (seat, seat_results) in self.seats.iter_mut().enumerate() {
+            seat_results.record(result, seat);
+        }
+        if result.is_tie() {
+            self.split_pots += 1;
+        }
+        for category in result.categories() {
+            *self.hand_rank_counts.entry(category).or_insert(0) += 1;
+        }
+        for aggregator in &mut self.aggregators {
+            aggregator.update(result);
+        }
+    }
+
+    fn merge(mut self, other: Totals) -> Totals {
+        for (seat, other_seat) in self.seats.iter_mut().zip(&other.seats) {
+            seat.merge(other_seat);
+        }
+        self.split_pots += other.split_pots;
+        for (category, count) in other.hand_rank_counts {
+            *self.hand_rank_counts.entry(category).or_insert(0) += count;
+        }
+        self.game_times.merge(&other.game_times);
+        if let (Some(profile), Some(other_profile)) = (&mut self.profile, &other.profile) {
+            profile.merge(other_profile);
+        }
+        for (aggregator, other) in self.aggregators.iter_mut().zip(other.aggregators) {
+            aggregator.merge(other);
+        }
+        self
+    }
+}
+
+pub struct SimulationStats {
+    // Fewer than `num_games` when the budget ran out first
+    pub games_played: usize,
+    pub seats: Vec<SeatResults>,
+    pub split_pots: usize,
+    // Showdown hands per category name, counted for every seat
+    pub hand_rank_counts: HashMap<&'static str, usize>,
+    pub game_times: Histogram,
+    pub profile: Option<Profile>,
+    // The aggregators passed to run_simulation, in the same order, holding every game
+    pub aggregators: Vec<Box<dyn Aggregator>>,
+    pub elapsed: Duration,
+    // Whether the run stopped because it reached `SimulationConfig::tolerance`
+    pub converged: bool,
+}
+
+// Play `config.num_games` games, or fewer if the budget runs out, passing every result to
+// `hooks` and `aggregators` and the statistics of every finished chunk to `on_chunk`
+pub fn run_simulation<C>(
+    config: &SimulationConfig,
+    hooks: &[&dyn HandHook],
+    aggregators: Vec<Box<dyn Aggregator>>,
+    mut on_chunk: C,
+) -> SimulationStats
+where
+    C: FnMut(&ChunkStats),
+{
+    let num_players = config.num_players;
+
+    // Games are played in chunks so the budget can be checked in between
+    let budget = Budget::new(config.max_seconds, config.max_iterations);
+    let mut games_played = 0;
+    let mut converged = false;
+    let mut totals = Totals {
+        aggregators,
+        ..Totals::new(num_players, config.profile, &[])
+    };
+    loop {
+        let chunk = budget.next_chunk(games_played, config.num_games, CHUNK_SIZE);
+        if chunk == 0 {
+            break;
+        }
+        let chunk_started = Instant::now();
+        let allocations_before = alloc_counter::allocations();
+        let chunk_totals = fold_batches(
+            games_played..games_played + chunk,
+            || Totals::new(num_players, config.profile, &totals.aggregators),
+            |mut local, games| {
+                // Seeded by the batch's first game, so results do not depend on which
+                // thread plays which batch
+                let mut rng = task_rng(config.seed, Purpose::Deal, games.start as u64);
+                let dealt = Instant::now();
+                let mut batch = GameBatch::deal_profiled(
+                    games.len(),
+                    num_players,
+                    config.variant,
+                    &mut rng,
+                    local.profile.as_mut(),
+                );
+                batch.draw(config.discard_policy.as_ref());
+                // Dealing is timed per batch, so each game is charged an equal share
+                let deal_nanos = dealt.elapsed().as_nanos() as u64 / batch.len() as u64;
+
+                for (i, game) in games.enumerate() {
+                    // Always on in debug builds; --paranoid keeps it in release builds
+                    if config.paranoid || cfg!(debug_assertions) {
+                        batch.check_distinct_cards(i);
+                    }
+                    let started = config.timing.then(Instant::now);
+                    let result = batch.play_profiled(
+                        i,
+                        config.fold_model.as_ref(),
+                        &mut rng,
+                        local.profile.as_mut(),
+                    );
+                    if let Some(started) = started {
+                        let nanos = started.elapsed().as_nanos() as u64 + deal_nanos;
+                        local.game_times.record(nanos);
+                    }
+
+                    let aggregate_started = Instant::now();
+                    local.record(&result);
+                    if let Some(profile) = &mut local.profile {
+                        profile.record(Phase::Aggregate, aggregate_started);
+                    }
+
+                    let hooks_started = Instant::now();
+                    for hook in hooks {
+                        hook.on_hand(game + 1, &result);
+                    }
+                    if let Some(profile) = &mut local.profile {
+                        profile.record(Phase::Hooks, hooks_started);
+                    }
+                }
+                local
+            },
+            Totals::merge,
+        );
+        on_chunk(&ChunkStats {
+            index: games_played / CHUNK_SIZE,
+            games: chunk,
+            seconds: chunk_started.elapsed().as_secs_f64(),
+            allocations: alloc_counter::allocations()
+                .zip(allocations_before)
+                .map(|(after, before)| after - before),
+            game_times: &chunk_totals.game_times,
+        });
+        games_played += chunk;
+        totals = totals.merge(chunk_totals);
+
+        // Variance estimates from a handful of games are too rough to stop on
+        if let Some(tolerance) = config.tolerance {
+            let margin = worst_equity_margin(&totals.seats, games_played);
+            if games_played >= MIN_CONVERGENCE_GAMES && margin <= tolerance {
+                converged = true;
+                break;
+            }
+        }
+    }
+
+    SimulationStats {
+        games_played,
+        seats: totals.seats,
+        split_pots: totals.split_pots,
+        hand_rank_counts: totals.hand_rank_counts,
+        game_times: totals.game_times,
+        profile: totals.profile,
+        aggregators: totals.aggregators,
+        elapsed: budget.elapsed(),
+        converged,
+    }
+}
@@ -0,0 +1,97 @@
+// Mergeable log-linear histogram for non-negative integer samples, in the style of
+// HDR histograms. Each power of two is split into 2^SUB_BITS linear buckets, so any
+// reported quantile is within about 3% of the true value while memory stays fixed
+// no matter how many samples are recorded.
+const SUB_BITS: u32 = 5;
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+const BUCKETS: usize = (64 - SUB_BITS as usize + 1) * SUB_BUCKETS;
+
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    sum: u128,
+    max: u64,
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let shift = 63 - value.leading_zeros() - SUB_BITS;
+    let sub = (value >> shift) as usize - SUB_BUCKETS;
+    ((shift as usize + 1) << SUB_BITS) + sub
+}
+
+// Smallest and largest value that land in a bucket
+fn bucket_bounds(index: usize) -> (u64, u64) {
+    let group = index >> SUB_BITS;
+    let sub = (index & (SUB_BUCKETS - 1)) as u64;
+    if group == 0 {
+        return (sub, sub);
+    }
+    let low = (SUB_BUCKETS as u64 + sub) << (group - 1);
+    (low, low + ((1u64 << (group - 1)) - 1))
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram::new()
+    }
+}
+
+impl Histogram {
+    pub fn new() -> Histogram {
+        Histogram {
+            counts: vec![0; BUCKETS],
+            total: 0,
+            sum: 0,
+            max: 0,
+        }
+    }
+
+    pub fn record(&mut self, value: u64) {
+        self.counts[bucket_index(value)] += 1;
+        self.total += 1;
+        self.sum += value as u128;
+        self.max = self.max.max(value);
+    }
+
+    // Combine samples recorded elsewhere, e.g. on another worker thread
+    pub fn merge(&mut self, other: &Histogram) {
+        for (count, other_count) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other_count;
+        }
+        self.total += other.total;
+        self.sum += other.sum;
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.sum as f64 / self.total as f64
+    }
+
+    // Approximate value below which a fraction `q` of the samples fall
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let (low, high) = bucket_bounds(index);
+                return (low + (high - low) / 2).min(self.max);
+            }
+        }
+        self.max
+    }
+}
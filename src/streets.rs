@@ -0,0 +1,95 @@
+use crate::aggregate::{downcast, Aggregator};
+use crate::filter::{category_value, CATEGORIES};
+use crate::variant::Variant;
+use crate::GameResult;
+
+type StreetCounts = [[usize; 10]; 3];
+
+// Category frequencies of every seat's best hand so far on the flop, turn and river,
+// e.g. how often a hand has at least a pair on the flop
+pub struct StreetStats {
+    variant: Variant,
+    counts: StreetCounts,
+    hands: usize,
+}
+
+impl StreetStats {
+    pub fn new(variant: Variant) -> StreetStats {
+        StreetStats {
+            variant,
+            counts: [[0; 10]; 3],
+            hands: 0,
+        }
+    }
+
+    pub fn counts(&self) -> StreetCounts {
+        self.counts
+    }
+}
+
+impl Default for StreetStats {
+    fn default() -> Self {
+        StreetStats::new(Variant::Holdem)
+    }
+}
+
+impl Aggregator for StreetStats {
+    fn empty(&self) -> Box<dyn Aggregator> {
+        Box::new(StreetStats::new(self.variant))
+    }
+
+    fn update(&mut self, result: &GameResult) {
+        for (seat, hole_cards) in result.hole_cards.iter().enumerate() {
+            for (street, street_counts) in self.counts.iter_mut().enumerate() {
+                // The river hand is the showdown hand, already evaluated
+                let category = if street == 2 {
+                    category_value(&result.hand_ranks[seat])
+                } else {
+                    let variant = result.variant;
+                    let (hole_cards, board) =
+                        variant.street_cards(hole_cards, &result.board, street);
+                    variant.evaluate(hole_cards, board).category() as i64
+                };
+                street_counts[category as usize] += 1;
+            }
+        }
+        self.hands += result.hole_cards.len();
+    }
+
+    fn merge(&mut self, other: Box<dyn Aggregator>) {
+        let other = downcast::<StreetStats>(other);
+        for (total, count) in self
+            .counts
+            .iter_mut()
+            .flatten()
+            .zip(other.counts.iter().flatten())
+        {
+            *total += count;
+        }
+        self.hands += other.hands;
+    }
+
+    fn report(&self) {
+        let hands = self.hands.max(1) as f64;
+        let width = CATEGORIES.iter().map(|name| name.len()).max().unwrap_or(0);
+
+        println!("\nHand categories by street (exactly / at least):");
+        print!("{:<width$}", "");
+        for street in self.variant.street_names() {
+            print!("   {:>18}", street);
+        }
+        println!();
+        for (category, name) in CATEGORIES.iter().enumerate() {
+            print!("{:<width$}", name);
+            for street_counts in &self.counts {
+                let exactly = street_counts[category] as f64 / hands * 100.0;
+                let at_least =
+                    street_counts[category..].iter().sum::<usize>() as f64 / hands * 100.0;
+                let exactly = format!("{:.3}%", exactly);
+                let at_least = format!("{:.2}%", at_least);
+                print!("   {:>8} / {:>7}", exactly, at_least);
+            }
+            println!();
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::aggregate::{downcast, Aggregator};
+use crate::bitboard::CardSet;
+use crate::filter::category_value;
+use crate::variant::Variant;
+use crate::{Card, GameResult};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// Hand classes: the categories, with draws to a straight or flush slotted in after one
+// pair, since they only replace weaker hands
+pub const CLASSES: [&str; 12] = [
+    "HighCard",
+    "OnePair",
+    "StraightDraw",
+    "FlushDraw",
+    "TwoPair",
+    "ThreeOfAKind",
+    "Straight",
+    "Flush",
+    "FullHouse",
+    "FourOfAKind",
+    "StraightFlush",
+    "RoyalFlush",
+];
+
+const STRAIGHT_DRAW: usize = 2;
+const FLUSH_DRAW: usize = 3;
+
+// Categories numbered as in filter::CATEGORIES
+const STRAIGHT: usize = 4;
+const FLUSH: usize = 5;
+const STRAIGHT_FLUSH: usize = 8;
+
+fn category_class(category: usize) -> usize {
+    if category > 1 {
+        category + 2
+    } else {
+        category
+    }
+}
+
+// Street pairs a matrix is kept for, as indexes into Variant::street_names
+const STEPS: [(usize, usize); 3] = [(0, 1), (1, 2), (0, 2)];
+
+type Matrix = [[usize; CLASSES.len()]; CLASSES.len()];
+
+// How each seat's hand class moves from the flop to the turn and river, e.g. how often a
+// flush draw on the flop is a flush by the river, written as CSV at the end
+pub struct TransitionStats {
+    variant: Variant,
+    deck: CardSet,
+    path: String,
+    matrices: [Matrix; 3],
+}
+
+impl TransitionStats {
+    pub fn new(variant: Variant, path: String) -> TransitionStats {
+        TransitionStats {
+            variant,
+            deck: variant.deck().cards().iter().copied().collect(),
+            path,
+            matrices: [[[0; CLASSES.len()]; CLASSES.len()]; 3],
+        }
+    }
+
+    // Counts from one class to another, indexed like STEPS
+    pub fn matrices(&self) -> [Matrix; 3] {
+        self.matrices
+    }
+
+    fn write_csv(&self) -> io::Result<()> {
+        let streets = self.variant.street_names();
+        let mut out = BufWriter::new(File::create(&self.path)?);
+        writeln!(
+            out,
+            "from_street,to_street,from_class,to_class,hands,probability"
+        )?;
+        for (&(from_street, to_street), matrix) in STEPS.iter().zip(&self.matrices) {
+            for (from, row) in matrix.iter().enumerate() {
+                let total: usize = row.iter().sum();
+                if total == 0 {
+                    continue;
+                }
+                for (to, &count) in row.iter().enumerate() {
+                    writeln!(
+                        out,
+                        "{},{},{},{},{},{:.6}",
+                        streets[from_street],
+                        streets[to_street],
+                        CLASSES[from],
+                        CLASSES[to],
+                        count,
+                        count as f64 / total as f64
+                    )?;
+                }
+            }
+        }
+        out.flush()
+    }
+}
+
+// Class of a hand that may have more cards to come: a high card or one pair hand that
+// one more card could turn into a flush or a straight counts as a draw to it
+fn hand_class(variant: Variant, hole_cards: &[Card], board: &[Card], unseen: CardSet) -> usize {
+    let category = variant.evaluate(hole_cards, board).category() as usize;
+    if category > 1 {
+        return category_class(category);
+    }
+    // Stud deals the next card to the player, the other games to the board
+    let improved = unseen.iter().map(|card| {
+        let value = match variant.board_cards() {
+            0 => variant.evaluate(&[hole_cards, &[card]].concat(), board),
+            _ => variant.evaluate(hole_cards, &[board, &[card]].concat()),
+        };
+        value.category() as usize
+    });
+    let mut draw = category;
+    for improved in improved {
+        if improved == FLUSH || improved >= STRAIGHT_FLUSH {
+            return FLUSH_DRAW;
+        }
+        if improved == STRAIGHT {
+            draw = STRAIGHT_DRAW;
+        }
+    }
+    draw
+}
+
+impl Aggregator for TransitionStats {
+    fn empty(&self) -> Box<dyn Aggregator> {
+        Box::new(TransitionStats {
+            matrices: [[[0; CLASSES.len()]; CLASSES.len()]; 3],
+            path: self.path.clone(),
+            ..*self
+        })
+    }
+
+    fn update(&mut self, result: &GameResult) {
+        let variant = result.variant;
+        for (seat, hole_cards) in result.hole_cards.iter().enumerate() {
+            let mut classes = [0; 3];
+            for (street, class) in classes.iter_mut().enumerate() {
+                // Nothing is left to draw to at showdown, so the last class is the category
+                // of the hand already evaluated
+                if street == 2 {
+                    *class = category_class(category_value(&result.hand_ranks[seat]) as usize);
+                    continue;
+                }
+                let (hole_cards, board) = variant.street_cards(hole_cards, &result.board, street);
+                let seen = CardSet::from(hole_cards) | CardSet::from(board);
+                *class = hand_class(variant, hole_cards, board, self.deck & !seen);
+            }
+            for (matrix, &(from, to)) in self.matrices.iter_mut().zip(&STEPS) {
+                matrix[classes[from]][classes[to]] += 1;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: Box<dyn Aggregator>) {
+        let other = downcast::<TransitionStats>(other);
+        for (total, count) in self
+            .matrices
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .zip(other.matrices.iter().flatten().flatten())
+        {
+            *total += count;
+        }
+    }
+
+    fn report(&self) {
+        match self.write_csv() {
+            Ok(()) => println!("\nStreet transitions written to {}", self.path),
+            Err(err) => eprintln!("warning: could not write {}: {}", self.path, err),
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use crate::bitboard::CardSet;
+use crate::lookup::{evaluate, evaluate_short_deck, HandValue};
+use crate::low::{evaluate_low_eight, LowHandRank};
+use crate::{best_five_cards, Card, Deck, HandRank};
+use itertools::Itertools;
+use std::fmt;
+use std::str::FromStr;
+
+// The rules of the game being simulated: how many hole cards each player gets and which
+// cards may make up their hand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Variant {
+    // Any five of two hole cards and the board
+    #[default]
+    Holdem,
+    // Exactly two of four hole cards with exactly three board cards
+    Omaha,
+    // Omaha with the pot split between the best high hand and the best eight-or-better
+    // low, also made from exactly two hole cards and three board cards
+    OmahaHiLo,
+    // Omaha with five and six hole cards, still using exactly two of them
+    Omaha5,
+    Omaha6,
+    // Five-card Omaha Hi-Lo
+    BigO,
+    // Five-card Omaha with the first flop card turned face up before the preflop betting;
+    // the card is dealt at once, so showdowns play exactly as in five-card Omaha
+    Courchevel,
+    // Four hole cards, two of which are discarded on the flop; the hand then plays as
+    // Hold'em with the two kept
+    Irish,
+    // Hold'em with twos through fives removed; a flush beats a full house and A-6-7-8-9
+    // is the lowest straight
+    ShortDeck,
+    // Seven Card Stud: seven cards of one's own and no board, best five of the seven
+    Stud,
+    // Five Card Draw: five cards each and no board, with one draw to replace any of them
+    Draw,
+}
+
+pub const VARIANTS: [Variant; 11] = [
+    Variant::Holdem,
+    Variant::Omaha,
+    Variant::OmahaHiLo,
+    Variant::Omaha5,
+    Variant::Omaha6,
+    Variant::BigO,
+    Variant::Courchevel,
+    Variant::Irish,
+    Variant::ShortDeck,
+    Variant::Stud,
+    Variant::Draw,
+];
+
+impl Variant {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Holdem => "holdem",
+            Variant::Omaha => "omaha",
+            Variant::OmahaHiLo => "omaha8",
+            Variant::Omaha5 => "plo5",
+            Variant::Omaha6 => "plo6",
+            Variant::BigO => "bigo",
+            Variant::Courchevel => "courchevel",
+            Variant::Irish => "irish",
+            Variant::ShortDeck => "shortdeck",
+            Variant::Stud => "stud",
+            Variant::Draw => "draw",
+        }
+    }
+
+    pub fn hole_cards(&self) -> usize {
+        match self {
+            Variant::Holdem | Variant::ShortDeck => 2,
+            Variant::Omaha | Variant::OmahaHiLo | Variant::Irish => 4,
+            Variant::Omaha5 | Variant::BigO | Variant::Courchevel | Variant::Draw => 5,
+            Variant::Omaha6 => 6,
+            Variant::Stud => 7,
+        }
+    }
+
+    // How many hole cards a hand must use, in games that fix it; the board makes up the
+    // rest of the five
+    pub fn hole_cards_used(&self) -> Option<usize> {
+        match self {
+            Variant::Omaha
+            | Variant::OmahaHiLo
+            | Variant::Omaha5
+            | Variant::Omaha6
+            | Variant::BigO
+            | Variant::Courchevel => Some(2),
+            Variant::Holdem
+            | Variant::ShortDeck
+            | Variant::Stud
+            | Variant::Irish
+            | Variant::Draw => None,
+        }
+    }
+
+    // The hole cards a player plays on with once the flop is out: in Irish the two that
+    // make the best hand with the flop, otherwise all of them
+    pub fn keep(&self, hole_cards: &[Card], board: &[Card]) -> Vec<Card> {
+        match self {
+            Variant::Irish => {
+                let flop = CardSet::from(&board[..3]);
+                hole_cards
+                    .iter()
+                    .copied()
+                    .combinations(2)
+                    .max_by_key(|kept| evaluate(CardSet::from(&kept[..]) | flop))
+                    .unwrap()
+            }
+            _ => hole_cards.to_vec(),
+        }
+    }
+
+    // A fresh, unshuffled deck for the game
+    pub fn deck(&self) -> Deck {
+        match self {
+            Variant::ShortDeck => Deck::short(),
+            _ => Deck::new(),
+        }
+    }
+
+    pub fn deck_size(&self) -> usize {
+        match self {
+            Variant::ShortDeck => 36,
+            _ => 52,
+        }
+    }
+
+    pub fn board_cards(&self) -> usize {
+        match self {
+            Variant::Stud | Variant::Draw => 0,
+            _ => 5,
+        }
+    }
+
+    // Most cards a player can replace at the draw
+    pub fn draw_cards(&self) -> usize {
+        match self {
+            Variant::Draw => 5,
+            _ => 0,
+        }
+    }
+
+    // Whether hands grow street by street, so they can be followed from the flop (or
+    // fifth street) to the river
+    pub fn has_streets(&self) -> bool {
+        *self != Variant::Draw
+    }
+
+    // Most players one deck can deal in, e.g. seven in Stud, as eight would need 56 cards.
+    // In draw games the replacements come from the same deck, so it also depends on how
+    // many cards each player may draw.
+    pub fn max_players(&self, max_draw: usize) -> usize {
+        let draw = max_draw.min(self.draw_cards());
+        (self.deck_size() - self.board_cards()) / (self.hole_cards() + draw)
+    }
+
+    // Names of the last three streets, which the hands are compared on
+    pub fn street_names(&self) -> [&'static str; 3] {
+        match self {
+            Variant::Stud => ["5th street", "6th street", "7th street"],
+            _ => ["Flop", "Turn", "River"],
+        }
+    }
+
+    // A player's cards and the board as of street `street` of `street_names`
+    pub fn street_cards<'a>(
+        &self,
+        hole_cards: &'a [Card],
+        board: &'a [Card],
+        street: usize,
+    ) -> (&'a [Card], &'a [Card]) {
+        match self {
+            Variant::Stud => (&hole_cards[..5 + street], board),
+            // One hand from start to finish
+            Variant::Draw => (hole_cards, board),
+            _ => (hole_cards, &board[..3 + street]),
+        }
+    }
+
+    // The best hand from `hole_cards` and `board`, which may be a flop or turn as well as a
+    // full board
+    pub fn evaluate(&self, hole_cards: &[Card], board: &[Card]) -> HandValue {
+        let cards = CardSet::from(hole_cards) | CardSet::from(board);
+        match (self, self.hole_cards_used()) {
+            (_, Some(used)) => exact_hands(hole_cards, board, used)
+                .map(evaluate)
+                .max()
+                .unwrap(),
+            (Variant::ShortDeck, None) => evaluate_short_deck(cards),
+            (_, None) => evaluate(cards),
+        }
+    }
+
+    // Whether the pot is split between the best high and the best low hand
+    pub fn is_hi_lo(&self) -> bool {
+        matches!(self, Variant::OmahaHiLo | Variant::BigO)
+    }
+
+    // The best qualifying low from `hole_cards` and a full `board`, always None in games
+    // without a low half
+    pub fn evaluate_low(&self, hole_cards: &[Card], board: &[Card]) -> Option<LowHandRank> {
+        match self.hole_cards_used() {
+            Some(used) if self.is_hi_lo() => exact_hands(hole_cards, board, used)
+                .filter_map(evaluate_low_eight)
+                .min(),
+            _ => None,
+        }
+    }
+
+    // How strong a hand is under this game's ranking, for comparing hands
+    pub fn strength(&self, hand_rank: &HandRank) -> HandValue {
+        let value = HandValue::from_hand_rank(hand_rank);
+        match self {
+            Variant::ShortDeck => value.to_short_deck(),
+            _ => value,
+        }
+    }
+
+    // The five cards that make the best hand, for display
+    pub fn best_five(&self, hole_cards: &[Card], board: &[Card]) -> Vec<Card> {
+        match (self, self.hole_cards_used()) {
+            (_, Some(used)) => hole_cards
+                .iter()
+                .copied()
+                .combinations(used)
+                .cartesian_product(board.iter().copied().combinations(5 - used))
+                .map(|(hole, board)| [hole, board].concat())
+                .max_by_key(|five| evaluate(five.iter().copied().collect()))
+                .unwrap(),
+            (Variant::ShortDeck, None) => [hole_cards, board]
+                .concat()
+                .into_iter()
+                .combinations(5)
+                .max_by_key(|five| evaluate_short_deck(five.iter().copied().collect()))
+                .unwrap(),
+            (_, None) => best_five_cards(&[hole_cards, board].concat()).1,
+        }
+    }
+}
+
+// Every hand of exactly `used` hole cards and as many board cards as make five
+fn exact_hands<'a>(
+    hole_cards: &'a [Card],
+    board: &[Card],
+    used: usize,
+) -> impl Iterator<Item = CardSet> + 'a {
+    let boards: Vec<CardSet> = board
+        .iter()
+        .copied()
+        .combinations(5 - used)
+        .map(CardSet::from_iter)
+        .collect();
+    hole_cards
+        .iter()
+        .copied()
+        .combinations(used)
+        .map(CardSet::from_iter)
+        .cartesian_product(boards)
+        .map(|(hole, board)| hole | board)
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Variant, String> {
+        VARIANTS
+            .iter()
+            .find(|variant| variant.name().eq_ignore_ascii_case(text))
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = VARIANTS.iter().map(Variant::name).collect();
+                format!(
+                    "unknown variant {}; expected one of {}",
+                    text,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_cards;
+    use crate::Rank;
+
+    fn best(variant: Variant, hole_cards: &str, board: &str) -> HandRank {
+        let hole_cards = parse_cards(hole_cards).unwrap();
+        let board = parse_cards(board).unwrap();
+        variant.evaluate(&hole_cards, &board).to_hand_rank()
+    }
+
+    #[test]
+    fn omaha_needs_two_hole_cards_for_a_flush() {
+        // One heart in hand on a four-heart board
+        let board = "2h 7h 9h Kh 3c";
+        assert!(matches!(
+            best(Variant::Holdem, "Ah Qs", board),
+            HandRank::Flush(_)
+        ));
+        assert_eq!(
+            best(Variant::Omaha, "Ah Qs Qd 4c", board),
+            HandRank::OnePair(Rank::Queen, [Rank::King, Rank::Nine, Rank::Seven])
+        );
+        // Two hearts in hand make it
+        assert!(matches!(
+            best(Variant::Omaha, "Ah 4h Qs Qd", board),
+            HandRank::Flush(_)
+        ));
+    }
+
+    #[test]
+    fn omaha_needs_two_hole_cards_for_a_straight() {
+        // Four board cards to a straight and one more in hand
+        let board = "5c 6d 7h 8s Kc";
+        assert_eq!(
+            best(Variant::Holdem, "9d 2c", board),
+            HandRank::Straight(Rank::Nine)
+        );
+        assert!(matches!(
+            best(Variant::Omaha, "9d 2c Jh Qs", board),
+            HandRank::HighCard(_)
+        ));
+        assert_eq!(
+            best(Variant::Omaha, "9d Ts 2c 3h", board),
+            HandRank::Straight(Rank::Ten)
+        );
+    }
+
+    fn low(variant: Variant, hole_cards: &str, board: &str) -> Option<String> {
+        let hole_cards = parse_cards(hole_cards).unwrap();
+        let board = parse_cards(board).unwrap();
+        variant
+            .evaluate_low(&hole_cards, &board)
+            .map(|low| low.to_string())
+    }
+
+    #[test]
+    fn hi_lo_low_uses_two_hole_cards() {
+        // Three low board cards and a single low hole card
+        let board = "2c 5d 7h Kc Qs";
+        assert_eq!(low(Variant::OmahaHiLo, "Ah Ks Qd Jc", board), None);
+        assert_eq!(low(Variant::BigO, "Ah Ks Qd Jc Tc", board), None);
+        // Four low board cards are no help either
+        assert_eq!(
+            low(Variant::OmahaHiLo, "Ah Ks Qd Jc", "2c 3d 5h 7c Qs"),
+            None
+        );
+        assert_eq!(
+            low(Variant::OmahaHiLo, "Ah 3s Qd Jc", board),
+            Some("7-5-3-2-A".to_string())
+        );
+        // Games without a low half never have one
+        assert_eq!(low(Variant::Omaha, "Ah 3s Qd Jc", board), None);
+        assert!(Variant::OmahaHiLo.is_hi_lo() && !Variant::Omaha.is_hi_lo());
+    }
+}
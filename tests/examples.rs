@@ -0,0 +1,141 @@
+// The calls the examples make, at test sizes and with their results checked, so the
+// examples cannot drift from the API without a failing test.
+
+use pokersim::aggregate::{downcast, Aggregator};
+use pokersim::equity::{self, Holding, Scenario};
+use pokersim::filter::category_value;
+use pokersim::folding::{chen_score, position_name, FoldModel, TablePreset};
+use pokersim::notation::parse_cards;
+use pokersim::range::Range;
+use pokersim::simulator::{self, SimulationConfig, MIN_CONVERGENCE_GAMES};
+use pokersim::GameResult;
+
+// examples/equity.rs
+#[test]
+fn equity_against_a_range() {
+    let hero = Holding::Known(parse_cards("Ah Kh").unwrap());
+    let villain = Holding::Range(Range::parse("QQ+,AQs+,JTs").unwrap());
+    let board = parse_cards("Qh 7h 2c").unwrap();
+    let scenario = Scenario::new(vec![hero, villain], board).unwrap();
+
+    let (results, deals) = equity::exact_equity_by_street(&scenario).unwrap();
+    let (full, full_deals) = equity::exact_equity(&scenario).unwrap();
+    assert_eq!(deals, full_deals);
+    let shares: Vec<f64> = results
+        .iter()
+        .map(|seat| seat.equity() / deals as f64)
+        .collect();
+    assert!((shares.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    for (seat, full) in results.iter().zip(&full) {
+        assert_eq!(seat.wins, full.wins);
+        assert_eq!(seat.ties, full.ties);
+    }
+    // 19 villain combos around the known cards, 990 turns and rivers each
+    assert_eq!(deals, 18_810);
+    assert_eq!(results[0].equity(), 9965.5);
+
+    // Sampling lands near the exact answer and repeats itself under the same seed
+    let trials = 20_000;
+    let sampled = equity::sample_equity(&scenario, trials, 42);
+    let again = equity::sample_equity(&scenario, trials, 42);
+    for ((seat, share), again) in sampled.iter().zip(&shares).zip(&again) {
+        let (equity, low, high) = seat.equity_interval(trials);
+        assert!(low <= equity && equity <= high);
+        assert!((equity - share).abs() < 4.0 * seat.equity_standard_error(trials));
+        assert_eq!(seat.wins, again.wins);
+    }
+}
+
+#[derive(Default)]
+struct WinningHands {
+    categories: [usize; 10],
+    favourite_won: usize,
+    pots: usize,
+}
+
+impl Aggregator for WinningHands {
+    fn empty(&self) -> Box<dyn Aggregator> {
+        Box::new(WinningHands::default())
+    }
+
+    fn update(&mut self, result: &GameResult) {
+        let winner = result.winners[0];
+        self.categories[category_value(&result.hand_ranks[winner]) as usize] += 1;
+        let favourite = (0..result.hole_cards.len())
+            .max_by(|&a, &b| {
+                let score = |seat: usize| chen_score(&result.hole_cards[seat]);
+                score(a).partial_cmp(&score(b)).unwrap()
+            })
+            .unwrap();
+        if result.winners.contains(&favourite) {
+            self.favourite_won += 1;
+        }
+        self.pots += 1;
+    }
+
+    fn merge(&mut self, other: Box<dyn Aggregator>) {
+        let other = downcast::<WinningHands>(other);
+        for (total, count) in self.categories.iter_mut().zip(other.categories) {
+            *total += count;
+        }
+        self.favourite_won += other.favourite_won;
+        self.pots += other.pots;
+    }
+
+    fn report(&self) {}
+}
+
+// examples/aggregator.rs
+#[test]
+fn custom_aggregator_sees_every_game() {
+    let config = SimulationConfig {
+        seed: 7,
+        ..SimulationConfig::new(20_000, 6)
+    };
+    let mut stats = simulator::run_simulation(
+        &config,
+        &[],
+        vec![Box::new(WinningHands::default())],
+        |_| {},
+    );
+    assert_eq!(stats.aggregators.len(), 1);
+    let winning = downcast::<WinningHands>(stats.aggregators.remove(0));
+    assert_eq!(winning.pots, stats.games_played);
+    assert_eq!(winning.categories.iter().sum::<usize>(), winning.pots);
+    // The best starting hand wins more than its one-in-six share
+    let favourite = winning.favourite_won as f64 / winning.pots as f64;
+    assert!(favourite > 1.0 / 6.0 && favourite < 0.5, "{}", favourite);
+}
+
+// examples/positions.rs
+#[test]
+fn positions_at_a_six_max_table() {
+    let players = 6;
+    let model = FoldModel::for_table(TablePreset::SixMax, players);
+    let names: Vec<String> = (0..players)
+        .map(|seat| position_name(seat, players))
+        .collect();
+    assert_eq!(names, ["UTG", "HJ", "CO", "BTN", "SB", "BB"]);
+    let vpips: Vec<f64> = (0..players).map(|seat| model.vpip(seat)).collect();
+    assert_eq!(vpips, [0.17, 0.21, 0.28, 0.45, 0.36, 1.0]);
+
+    let config = SimulationConfig {
+        fold_model: Some(model),
+        tolerance: Some(0.01),
+        seed: 1,
+        ..SimulationConfig::new(2_000_000, players)
+    };
+    let stats = simulator::run_simulation(&config, &[], Vec::new(), |_| {});
+    let games = stats.games_played;
+    assert!(stats.converged);
+    assert!((MIN_CONVERGENCE_GAMES..2_000_000).contains(&games));
+    let equities: Vec<f64> = stats
+        .seats
+        .iter()
+        .map(|seat| seat.equity_interval(games).0)
+        .collect();
+    assert!((equities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    // The big blind never folds, so it takes the most pots, and the tightest seat the
+    // fewest
+    assert!(equities[5] > equities[3] && equities[3] > equities[0]);
+}